@@ -1,31 +1,79 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use ethers::abi::HumanReadableParser;
+use l1x_sdk::contract_interaction::ContractCall;
 use l1x_sdk::types::Address;
-use l1x_sdk::{contract, store::LookupMap, types::U64};
+use l1x_sdk::{
+    contract,
+    store::LookupMap,
+    types::{U128, U64},
+};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
 
+/// Key of the unversioned registry written by storage version 1
 const STORAGE_CONTRACT_KEY: &[u8] = b"REGISTRY";
+/// Key of the storage envelope: the Borsh-encoded `u32` storage version
+/// followed by the Borsh-encoded registry
+const STORAGE_STATE_KEY: &[u8] = b"REGSTATE";
+/// Key of the `MigrationV1` progress of a version 1 registry being migrated
+const STORAGE_MIGRATION_KEY: &[u8] = b"REGMIGRATION";
 const REGISTRY_SOURCES: &[u8] = b"REGKEY";
+const REGISTRY_ADMIN_OPS: &[u8] = b"REGADMINOPS";
+const REGISTRY_SOURCE_OWNERS: &[u8] = b"REGSRCOWNERS";
+const REGISTRY_ACTIVE_SOURCES: &[u8] = b"REGACTIVE";
+const REGISTRY_SOURCE_IDENTITIES: &[u8] = b"REGIDENTITY";
+const REGISTRY_SOURCE_LOOKUP: &[u8] = b"REGLOOKUP";
+const REGISTRY_CHAINS: &[u8] = b"REGCHAINS";
+const REGISTRY_FLOW_SOURCES: &[u8] = b"REGFLOWSRC";
+const REGISTRY_CHAIN_SOURCES: &[u8] = b"REGCHAINSRC";
+const REGISTRY_CHAIN_VERSIONS: &[u8] = b"REGCHAINVER";
+const REGISTRY_SOURCE_IDS: &[u8] = b"REGSRCIDS";
+const REGISTRY_DEPOSITS: &[u8] = b"REGDEPOSITS";
+const REGISTRY_ACTIVE_IDS: &[u8] = b"REGACTIVEIDS";
 
+/// Upper bound on the number of items returned by a single paged query
+const MAX_PAGE_SIZE: u32 = 100;
+
+/// Number of consecutive ids sharing a bucket of `active_buckets`
+const ACTIVE_BUCKET_SIZE: u64 = MAX_PAGE_SIZE as u64;
+
+/// Number of version 1 ops replayed by a single `migrate` call
+const MIGRATION_BATCH_SIZE: u64 = 500;
+
+/// Version of the `RegistryManifest` format written by `export_manifest`
+const MANIFEST_VERSION: u32 = 1;
+
+/// Storage version written by `save`
+///
+/// Any change to the Borsh layout of `SourceRegistry` or of the values of
+/// its maps needs a new version, with a step in `migrate` upgrading
+/// registries stored by the previous one
+const STORAGE_VERSION: u32 = 2;
+
+/// Address and event format family of a chain
 #[derive(
     Serialize,
     Deserialize,
     Clone,
+    Copy,
     Debug,
     Eq,
     PartialEq,
     BorshSerialize,
     BorshDeserialize,
 )]
-pub struct EventSource {
-    pub flow_contract_address: String,
-    pub source_id: String,
-    pub chain: String,
-    pub source_type: String,
-    pub smart_contract_address: String,
-    pub event_type: String,
-    pub event_filters: Vec<String>,
+pub enum Compatibility {
+    Evm,
+    Solana,
+    L1x,
 }
 
+/// Identifier of a chain in the chain catalog, e.g. "ethereum_goerli"
+///
+/// Encoded exactly like a `String`, both in JSON and in Borsh
 #[derive(
     Serialize,
     Deserialize,
@@ -33,14 +81,124 @@ pub struct EventSource {
     Debug,
     Eq,
     PartialEq,
+    PartialOrd,
+    Ord,
+    Hash,
     BorshSerialize,
     BorshDeserialize,
 )]
-pub enum Operation {
-    Create,
-    Remove,
+#[serde(transparent)]
+pub struct ChainId(pub String);
+
+impl ChainId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for ChainId {
+    fn from(name: &str) -> Self {
+        Self(name.to_string())
+    }
+}
+
+impl fmt::Display for ChainId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Kind of on-chain activity a source watches
+///
+/// Encoded as its snake_case name, both in JSON and in Borsh, so entries
+/// stored while `source_type` was a free-form `String` still decode. Names
+/// that match no kind decode as `Unknown` and are rejected on registration
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SourceType {
+    SmartContractEvent,
+    BlockHeader,
+    NativeTransfer,
+    SolanaProgramLog,
+    L1xContractEvent,
+    Unknown(String),
+}
+
+impl SourceType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::SmartContractEvent => "smart_contract_event",
+            Self::BlockHeader => "block_header",
+            Self::NativeTransfer => "native_transfer",
+            Self::SolanaProgramLog => "solana_program_log",
+            Self::L1xContractEvent => "l1x_contract_event",
+            Self::Unknown(name) => name,
+        }
+    }
+
+    /// Chain family the source kind can be watched on
+    fn compatibility(&self) -> Option<Compatibility> {
+        match self {
+            Self::SmartContractEvent
+            | Self::BlockHeader
+            | Self::NativeTransfer => Some(Compatibility::Evm),
+            Self::SolanaProgramLog => Some(Compatibility::Solana),
+            Self::L1xContractEvent => Some(Compatibility::L1x),
+            Self::Unknown(_) => None,
+        }
+    }
+}
+
+impl From<&str> for SourceType {
+    fn from(name: &str) -> Self {
+        match name {
+            "smart_contract_event" => Self::SmartContractEvent,
+            "block_header" => Self::BlockHeader,
+            "native_transfer" => Self::NativeTransfer,
+            "solana_program_log" => Self::SolanaProgramLog,
+            "l1x_contract_event" => Self::L1xContractEvent,
+            _ => Self::Unknown(name.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for SourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for SourceType {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for SourceType {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let name = <String as Deserialize>::deserialize(deserializer)?;
+        Ok(Self::from(name.as_str()))
+    }
+}
+
+impl BorshSerialize for SourceType {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(self.as_str(), writer)
+    }
+}
+
+impl BorshDeserialize for SourceType {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let name = <String as BorshDeserialize>::deserialize(buf)?;
+        Ok(Self::from(name.as_str()))
+    }
 }
 
+/// When a listener may forward an event of a source to its flow
 #[derive(
     Serialize,
     Deserialize,
@@ -51,128 +209,3286 @@ pub enum Operation {
     BorshSerialize,
     BorshDeserialize,
 )]
-pub struct EventSourceOp {
-    pub event_source: EventSource,
-    pub op: Operation,
+pub enum FinalityPolicy {
+    /// Once the event's block has this many blocks on top of it
+    Confirmations(u32),
+    /// Once the event's block is at or below the "safe" block tag
+    Safe,
+    /// Once the event's block is at or below the "finalized" block tag
+    Finalized,
+    /// Once the rollup batch holding the event's block is finalized on L1.
+    /// Only for rollups, see `ChainInfo::parent_chain`
+    L1Finalized,
 }
 
-#[derive(BorshSerialize, BorshDeserialize)]
-pub struct SourceRegistry {
-    pub sources: LookupMap<U64, EventSourceOp>,
-    pub index: U64,
+impl FinalityPolicy {
+    /// Whether listeners can apply the policy on `chain`
+    fn is_supported_on(&self, chain: &ChainInfo) -> bool {
+        match self {
+            Self::Confirmations(_) => true,
+            Self::Safe | Self::Finalized => {
+                chain.compatibility == Compatibility::Evm
+            }
+            Self::L1Finalized => {
+                chain.compatibility == Compatibility::Evm
+                    && chain.parent_chain.is_some()
+            }
+        }
+    }
 }
 
-#[contract]
-impl SourceRegistry {
-    pub fn new() {
-        let mut contract = SourceRegistry {
-            sources: LookupMap::new(REGISTRY_SOURCES.to_vec()),
-            index: U64::from(0),
-        };
-        contract.save();
+/// Chain catalog entry, describing a chain listeners can watch
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct ChainInfo {
+    /// Name used as `EventSource::chain`, e.g. "ethereum_goerli"
+    pub name: ChainId,
+    pub compatibility: Compatibility,
+    /// Numeric chain id, e.g. EIP-155 chain id for EVM chains
+    pub chain_id: u64,
+    /// Average block time in milliseconds
+    pub block_time_ms: u64,
+    /// Confirmations to wait for before delivering an event
+    pub default_confirmations: u32,
+    /// Whether new sources can be registered on the chain
+    pub enabled: bool,
+    /// L1 chain a rollup settles on, `None` for chains that aren't rollups
+    #[serde(default)]
+    pub parent_chain: Option<ChainId>,
+}
+
+impl ChainInfo {
+    /// Finality policy of sources registered without one
+    fn default_finality(&self) -> FinalityPolicy {
+        FinalityPolicy::Confirmations(self.default_confirmations)
     }
 
-    fn load() -> Self {
-        match l1x_sdk::storage_read(STORAGE_CONTRACT_KEY) {
-            Some(bytes) => Self::try_from_slice(&bytes).unwrap(),
-            None => panic!("The contract isn't initialized"),
+    /// Catalog a freshly deployed registry starts with
+    fn defaults() -> Vec<ChainInfo> {
+        vec![
+            ChainInfo {
+                name: ChainId::from("ethereum_goerli"),
+                compatibility: Compatibility::Evm,
+                chain_id: 5,
+                block_time_ms: 12_000,
+                default_confirmations: 12,
+                enabled: true,
+                parent_chain: None,
+            },
+            ChainInfo {
+                name: ChainId::from("optimism_goerli"),
+                compatibility: Compatibility::Evm,
+                chain_id: 420,
+                block_time_ms: 2_000,
+                default_confirmations: 10,
+                enabled: true,
+                parent_chain: Some(ChainId::from("ethereum_goerli")),
+            },
+        ]
+    }
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct EventSource {
+    pub flow_contract_address: String,
+    pub source_id: String,
+    pub chain: ChainId,
+    pub source_type: SourceType,
+    pub smart_contract_address: String,
+    pub event_type: String,
+    pub event_filters: TopicFilter,
+    /// First block (slot on Solana) to scan, backfilling from there when it
+    /// is in the past. `None` starts from the chain head
+    pub from_block: Option<U64>,
+    /// Last block (slot on Solana) to scan, after which the source expires.
+    /// `None` never expires
+    pub to_block: Option<U64>,
+    /// When listeners may forward an event of the source
+    pub finality: FinalityPolicy,
+    /// Hash of the event signature, derived by the registry from
+    /// `event_type` for EVM contract events
+    pub topic0: Option<String>,
+}
+
+/// Source to register, as provided by the caller
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct NewEventSource {
+    pub flow_contract_address: String,
+    pub source_id: String,
+    pub chain: ChainId,
+    pub source_type: SourceType,
+    pub smart_contract_address: String,
+    pub event_type: String,
+    pub event_filters: TopicFilter,
+    pub from_block: Option<U64>,
+    pub to_block: Option<U64>,
+    /// `None` uses the default finality of the chain
+    pub finality: Option<FinalityPolicy>,
+}
+
+/// Topic filter of an EVM log subscription, following the `eth_getLogs`
+/// model
+///
+/// Position `i` constrains topic `i` of a log, for up to four positions:
+/// `None` matches any value and a set matches any of its values. In JSON,
+/// e.g. `[["0x<topic0>"], null, ["0x<a>", "0x<b>"]]`. The legacy flat list
+/// of topics, e.g. `["0x<a>", "0x<b>"]`, is read as a topic0 set
+#[derive(
+    Serialize,
+    Clone,
+    Debug,
+    Default,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[serde(transparent)]
+pub struct TopicFilter(pub Vec<Option<Vec<String>>>);
+
+impl TopicFilter {
+    /// Number of topics an EVM log can carry
+    const MAX_POSITIONS: usize = 4;
+
+    /// Migrate a legacy flat filter list, whose entries were alternatives
+    /// for topic0
+    pub fn from_flat(filters: Vec<String>) -> Self {
+        if filters.is_empty() {
+            return Self::default();
         }
+        Self(vec![Some(filters)])
     }
 
-    fn save(&mut self) {
-        let encoded_contract = borsh::BorshSerialize::try_to_vec(self).unwrap();
-        l1x_sdk::storage_write(STORAGE_CONTRACT_KEY, &encoded_contract);
+    /// Positions with lowercase, sorted and deduplicated sets and no
+    /// trailing wildcards, so equivalent filters compare equal
+    fn normalized(&self) -> Vec<Option<Vec<String>>> {
+        let mut positions: Vec<Option<Vec<String>>> = self
+            .0
+            .iter()
+            .map(|position| {
+                position.as_ref().map(|values| {
+                    let mut values: Vec<String> = values
+                        .iter()
+                        .map(|value| EventSource::normalize_hex(value))
+                        .collect();
+                    values.sort();
+                    values.dedup();
+                    values
+                })
+            })
+            .collect();
+        while positions.last().is_some_and(|position| position.is_none()) {
+            positions.pop();
+        }
+        positions
     }
 
-    pub fn register_new_source(
-        flow_contract_address: String,
-        source_id: String,
-        chain: String,
-        source_type: String,
-        smart_contract_address: String,
-        event_type: String,
-        event_filters: Vec<String>,
-    ) -> Result<U64, String> {
-        let mut contract = Self::load();
-        let new_source: EventSource = EventSource {
-            flow_contract_address,
-            source_id,
-            chain,
-            source_type,
-            smart_contract_address,
-            event_type,
-            event_filters,
-        };
-        let source_op = EventSourceOp {
-            event_source: new_source.clone(),
-            op: Operation::Create,
-        };
+    /// Check the filter's shape and values
+    ///
+    /// - `topic0`: Topic0 derived from the source's event signature, which
+    ///   a topic0 set must allow
+    fn validate(&self, topic0: Option<&String>) -> Result<(), RegistryError> {
+        if self.0.len() > Self::MAX_POSITIONS {
+            return Err(RegistryError::InvalidTopicFilter(format!(
+                "{} positions, at most {} allowed",
+                self.0.len(),
+                Self::MAX_POSITIONS
+            )));
+        }
+        for (position, values) in self.0.iter().enumerate() {
+            let Some(values) = values else { continue };
+            if values.is_empty() {
+                return Err(RegistryError::InvalidTopicFilter(format!(
+                    "empty set at position {}",
+                    position
+                )));
+            }
+            if let Some(value) = values
+                .iter()
+                .find(|value| !EventSource::is_hex_of_len(value, 32))
+            {
+                return Err(RegistryError::InvalidEventFilter(value.clone()));
+            }
+        }
 
-        let index = {
-            contract.index.0 = contract.index.0 + 1;
-            contract.index
-        };
-        contract.sources.set(index, Some(source_op));
-        contract.save();
-        Ok(index)
+        if let (Some(topic0), Some(Some(values))) = (topic0, self.0.first()) {
+            let topic0 = EventSource::normalize_hex(topic0);
+            if !values
+                .iter()
+                .any(|value| EventSource::normalize_hex(value) == topic0)
+            {
+                return Err(RegistryError::InvalidTopicFilter(format!(
+                    "topic0 set excludes the event signature hash 0x{}",
+                    topic0
+                )));
+            }
+        }
+        Ok(())
     }
+}
 
-    pub fn unregister_source(index: U64) {
-        let mut contract = Self::load();
-        if let Some(source_op) = contract.sources.get(&index) {
-            let mut source_op = source_op.clone();
-            source_op.op = Operation::Remove;
+impl<'de> Deserialize<'de> for TopicFilter {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Encoded {
+            Positions(Vec<Option<Vec<String>>>),
+            Flat(Vec<String>),
+        }
 
-            let index = {
-                contract.index.0 = contract.index.0 + 1;
-                contract.index
-            };
-            contract.sources.set(index, Some(source_op));
-            contract.save();
+        Ok(match Encoded::deserialize(deserializer)? {
+            Encoded::Positions(positions) => Self(positions),
+            Encoded::Flat(filters) => Self::from_flat(filters),
+        })
+    }
+}
+
+/// Canonical Solidity event signature and its topic0 hash
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct EventSignature {
+    /// e.g. "SwapExecuted(bytes32,address,address,uint256,address)"
+    pub signature: String,
+    /// `0x`-prefixed keccak256 hash of `signature`
+    pub topic0: String,
+}
+
+impl EventSignature {
+    /// Parse a human-readable event declaration, with or without the
+    /// `event` keyword, parameter names and `indexed` markers
+    fn parse(declaration: &str) -> Result<Self, RegistryError> {
+        let event =
+            HumanReadableParser::parse_event(declaration).map_err(|error| {
+                RegistryError::InvalidEventSignature(error.to_string())
+            })?;
+        let params: Vec<String> =
+            event.inputs.iter().map(|param| param.kind.to_string()).collect();
+        let signature = format!("{}({})", event.name, params.join(","));
+        let topic0 = format!(
+            "0x{}",
+            hex::encode(ethers::utils::keccak256(signature.as_bytes()))
+        );
+        Ok(Self { signature, topic0 })
+    }
+}
+
+impl EventSource {
+    /// Hash of the borsh-encoded `parts`, hex encoded
+    fn hash_key<T: BorshSerialize>(parts: &T) -> String {
+        let encoded = borsh::BorshSerialize::try_to_vec(parts)
+            .expect("Unable to encode key parts");
+        hex::encode(Sha256::digest(&encoded))
+    }
+
+    /// Lowercase hex without `0x` prefix, so equal values compare equal
+    fn normalize_hex(value: &str) -> String {
+        let value = value.trim();
+        let value = value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+            .unwrap_or(value);
+        value.to_ascii_lowercase()
+    }
+
+    /// Key shared by all sources of a flow that watch the same event type
+    /// of the same contract, whatever their filters
+    fn lookup_key(
+        flow_contract_address: &str,
+        chain: &ChainId,
+        smart_contract_address: &str,
+        event_type: &str,
+    ) -> String {
+        Self::hash_key(&(
+            Self::normalize_hex(flow_contract_address),
+            chain.as_str().trim().to_ascii_lowercase(),
+            Self::normalize_hex(smart_contract_address),
+            event_type.trim().to_string(),
+        ))
+    }
+
+    /// Key of a flow's source id, unique among the flow's active sources
+    fn source_id_key(flow_contract_address: &str, source_id: &str) -> String {
+        Self::hash_key(&(
+            Self::normalize_hex(flow_contract_address),
+            source_id.trim().to_string(),
+        ))
+    }
+
+    /// Canonical identity of the source. Two sources with the same identity
+    /// would deliver every event twice to the same flow
+    fn identity(&self) -> String {
+        Self::hash_key(&(
+            self.source_lookup_key(),
+            self.source_type.as_str(),
+            self.event_filters.normalized(),
+        ))
+    }
+
+    /// Whether `value` is `0x` followed by `len` bytes of hex
+    fn is_hex_of_len(value: &str, len: usize) -> bool {
+        value.strip_prefix("0x").is_some_and(|digits| {
+            digits.len() == len * 2
+                && digits.chars().all(|c| c.is_ascii_hexdigit())
+        })
+    }
+
+    /// Canonicalize the event signature of EVM contract events in
+    /// `event_type` and derive their `topic0`
+    ///
+    /// - `chain`: Catalog entry of the source's chain
+    fn derive_topic0(
+        &mut self,
+        chain: &ChainInfo,
+    ) -> Result<(), RegistryError> {
+        self.topic0 = None;
+        if self.source_type == SourceType::SmartContractEvent
+            && chain.compatibility == Compatibility::Evm
+        {
+            let event_signature = EventSignature::parse(&self.event_type)?;
+            self.event_type = event_signature.signature;
+            self.topic0 = Some(event_signature.topic0);
         }
+        Ok(())
     }
 
-    pub fn get_sources_from(from_index: U64) -> (u64, Vec<EventSourceOp>) {
-        let contract = Self::load();
-        let mut sources: Vec<EventSourceOp> = vec![];
-        let mut from_index = from_index;
-        let to_index = contract.index.0;
-        for index in from_index.0..to_index {
-            if let Some(source_op) = contract.sources.get(&U64::from(index)) {
-                sources.push(source_op.clone());
-                from_index.0 += 1;
+    /// Signature and topic0 derived by `derive_topic0`, if any
+    fn event_signature(&self) -> Option<EventSignature> {
+        self.topic0.as_ref().map(|topic0| EventSignature {
+            signature: self.event_type.clone(),
+            topic0: topic0.clone(),
+        })
+    }
+
+    /// Check the source's fields before it is stored
+    ///
+    /// - `chain`: Catalog entry of the source's chain
+    fn validate(&self, chain: &ChainInfo) -> Result<(), RegistryError> {
+        Address::try_from(self.flow_contract_address.clone()).map_err(
+            |error| RegistryError::InvalidFlowAddress(error.to_string()),
+        )?;
+        if self.source_id.trim().is_empty() {
+            return Err(RegistryError::InvalidSourceId(self.source_id.clone()));
+        }
+
+        match self.source_type.compatibility() {
+            None => {
+                return Err(RegistryError::UnsupportedSourceType(
+                    self.source_type.clone(),
+                ))
+            }
+            Some(compatibility) if compatibility != chain.compatibility => {
+                return Err(RegistryError::IncompatibleSourceType {
+                    source_type: self.source_type.clone(),
+                    chain: chain.name.clone(),
+                })
             }
+            Some(_) => {}
         }
-        (from_index.into(), sources)
+
+        if chain.compatibility == Compatibility::Evm {
+            // Only contract events need a contract, other kinds may narrow
+            // down to one
+            let needs_contract = self.source_type
+                == SourceType::SmartContractEvent
+                || !self.smart_contract_address.is_empty();
+            if needs_contract
+                && !Self::is_hex_of_len(&self.smart_contract_address, 20)
+            {
+                return Err(RegistryError::InvalidContractAddress(
+                    self.smart_contract_address.clone(),
+                ));
+            }
+            self.event_filters.validate(self.topic0.as_ref())?;
+        }
+
+        if !self.finality.is_supported_on(chain) {
+            return Err(RegistryError::UnsupportedFinality {
+                finality: self.finality.clone(),
+                chain: chain.name.clone(),
+            });
+        }
+
+        if let (Some(from_block), Some(to_block)) =
+            (self.from_block, self.to_block)
+        {
+            if from_block.0 > to_block.0 {
+                return Err(RegistryError::InvalidBlockRange {
+                    from_block,
+                    to_block,
+                });
+            }
+        }
+        Ok(())
     }
 
-    pub fn get_source(index: U64) -> Option<EventSourceOp> {
-        let contract = Self::load();
-        contract.sources.get(&index).cloned()
+    /// Key of the source's flow in the by-flow index
+    fn flow_key(&self) -> String {
+        Self::normalize_hex(&self.flow_contract_address)
+    }
+
+    fn flow_source_id_key(&self) -> String {
+        Self::source_id_key(&self.flow_contract_address, &self.source_id)
+    }
+
+    fn source_lookup_key(&self) -> String {
+        Self::lookup_key(
+            &self.flow_contract_address,
+            &self.chain,
+            &self.smart_contract_address,
+            &self.event_type,
+        )
+    }
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub enum Operation {
+    Create,
+    Remove,
+    /// The source was modified in place, carrying the changed fields
+    Update(SourceUpdate),
+    /// Event delivery for the source is suspended
+    Pause,
+    /// Event delivery for a paused source is back on
+    Resume,
+    /// The source was handed over to the given owner
+    TransferOwnership(Address),
+}
+
+/// Fields of an `EventSource` that can change without changing its identity
+///
+/// `None` leaves the field as is
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Default,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct SourceUpdate {
+    pub smart_contract_address: Option<String>,
+    pub event_type: Option<String>,
+    pub event_filters: Option<TopicFilter>,
+    pub finality: Option<FinalityPolicy>,
+}
+
+impl SourceUpdate {
+    /// Overwrite the fields of `event_source` set in the update
+    fn apply(self, event_source: &mut EventSource) {
+        if let Some(smart_contract_address) = self.smart_contract_address {
+            event_source.smart_contract_address = smart_contract_address;
+        }
+        if let Some(event_type) = self.event_type {
+            event_source.event_type = event_type;
+        }
+        if let Some(event_filters) = self.event_filters {
+            event_source.event_filters = event_filters;
+        }
+        if let Some(finality) = self.finality {
+            event_source.finality = finality;
+        }
+    }
+
+    /// Fields that differ between `previous` and `current`, with their
+    /// current values
+    fn diff(previous: &EventSource, current: &EventSource) -> SourceUpdate {
+        fn changed<T: PartialEq + Clone>(
+            previous: &T,
+            current: &T,
+        ) -> Option<T> {
+            (previous != current).then(|| current.clone())
+        }
+
+        SourceUpdate {
+            smart_contract_address: changed(
+                &previous.smart_contract_address,
+                &current.smart_contract_address,
+            ),
+            event_type: changed(&previous.event_type, &current.event_type),
+            event_filters: changed(
+                &previous.event_filters,
+                &current.event_filters,
+            ),
+            finality: changed(&previous.finality, &current.finality),
+        }
     }
 }
 
-// this is to verify that the caller is allowed to register source. need "get_contract_admins" to be implemented.
-//
-// let call = l1x_sdk::contract_interaction::ContractCall {
-//     contract_address: l1x_sdk::types::Address::try_from(new_source.clone().flow_contract_address).map_err(|e| e.to_string())?,
-//     method_name: "get_contract_admins".to_string(),
-//     args: vec![],
-//     read_only: true,
-//     fee_limit: 0,
-// };
-// let res = l1x_sdk::call_contract(&call).ok_or("Failed to call contract")?;
-// let authorized_addresses = serde_json::from_slice::<Vec<Address>>(&res).map_err(|e| e.to_string())?;
-//
-// let caller_address = l1x_sdk::caller_address();
-// if !authorized_addresses.contains(&caller_address) {
-//     return Err("Caller is not authorized to register new source".into());
-// }
-
-// contract.sources
-// let mut current_sources: Vec<EventSource> =
-//     if let Some(recent_sources) = l1x_sdk::storage_read(REGISTRY_SOURCES) {
-//         serde_json::from_slice(&recent_sources).map_err(|e| e.to_string())?
-//     } else {
-//         vec![]
-//     };
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct EventSourceOp {
+    /// Id of the affected source, i.e. the index of its `Create` op
+    pub id: U64,
+    /// State of the source after the op
+    pub event_source: EventSource,
+    pub op: Operation,
+}
+
+/// Currently registered source, identified by the index of its `Create` op
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct ActiveSource {
+    pub id: U64,
+    pub event_source: EventSource,
+    /// Listeners should not deliver events of a paused source
+    pub paused: bool,
+}
+
+/// Source op together with its op log index
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct IndexedSourceOp {
+    pub index: U64,
+    pub source_op: EventSourceOp,
+}
+
+/// Index a source was registered under
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct RegisteredSource {
+    pub id: U64,
+    /// Signature and topic0 derived for EVM contract events, `None` for
+    /// other sources
+    pub event_signature: Option<EventSignature>,
+}
+
+/// Deposit held for an active source
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct SourceDeposit {
+    /// Account that paid the deposit, which gets it back
+    pub depositor: Address,
+    pub amount: U128,
+}
+
+/// L1X event emitted for every op appended to the op log, so listeners can
+/// subscribe instead of polling `get_sources_from`
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub enum RegistryEvent {
+    /// Emitted when a source is registered
+    SourceRegistered(IndexedSourceOp),
+    /// Emitted when a source is removed
+    SourceRemoved(IndexedSourceOp),
+    /// Emitted when a source is updated, paused or resumed
+    SourceUpdated(IndexedSourceOp),
+    /// Emitted when registry admins, settings or chains change
+    AdminChanged { index: U64, admin_op: AdminOp },
+}
+
+/// Page of the op log
+///
+/// When `resync` is set, the requested cursor points into history pruned
+/// by `compact`: `ops` is empty and the client must rebuild its view from
+/// `get_active_sources`, then resume from `ops.next_cursor`
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct SourceLog {
+    /// Op log indices up to this one have been compacted
+    pub compacted_to: U64,
+    pub resync: bool,
+    pub ops: Page<IndexedSourceOp>,
+}
+
+/// Page of the active sources of a chain
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct ChainSources {
+    /// Version of the chain's source set when the page was read
+    pub version: U64,
+    pub sources: Page<ActiveSource>,
+}
+
+/// One page of a cursor-based query
+///
+/// `next_cursor` is the cursor to pass to get the following page and
+/// `has_more` tells whether there is one
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: U64,
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Page of op log entries, oldest first
+    ///
+    /// Covers the indices in `cursor + 1..=cursor + limit`, clamped to
+    /// `last_index`. `next_cursor` is the last covered index, even when
+    /// nothing was returned, and `has_more` tells whether the log goes past
+    /// it
+    ///
+    /// - `entry`: Reads the entry at an index, `None` when the index holds
+    ///   no entry of interest
+    fn from_log(
+        cursor: U64,
+        limit: usize,
+        last_index: u64,
+        entry: impl FnMut(U64) -> Option<T>,
+    ) -> Self {
+        let end = last_index.min(cursor.0.saturating_add(limit as u64));
+        let items = (cursor.0.saturating_add(1)..=end)
+            .map(U64::from)
+            .filter_map(entry)
+            .collect();
+        let next_cursor = U64::from(end.max(cursor.0));
+        Page { items, next_cursor, has_more: next_cursor.0 < last_index }
+    }
+}
+
+impl Page<U64> {
+    /// Page of the ids stored in buckets of `bucket_size` consecutive ids,
+    /// bucket `b` holding the ids in `b * bucket_size..(b + 1) * bucket_size`
+    /// in ascending order
+    ///
+    /// At most `limit` buckets are read, so a page may hold fewer than
+    /// `limit` ids while more follow. `next_cursor` is the last id covered,
+    /// never past `last_id`, and `has_more` tells whether ids past it
+    /// remain to be covered
+    ///
+    /// - `bucket`: Reads the ids of a bucket
+    fn from_buckets(
+        cursor: U64,
+        limit: usize,
+        bucket_size: u64,
+        last_id: u64,
+        mut bucket: impl FnMut(u64) -> Vec<U64>,
+    ) -> Self {
+        let mut items = vec![];
+        let mut next_cursor = cursor.0;
+        let first_bucket = cursor.0.saturating_add(1) / bucket_size;
+        for index in (first_bucket..=last_id / bucket_size).take(limit) {
+            if items.len() == limit {
+                break;
+            }
+            for id in bucket(index).into_iter().filter(|id| id.0 > cursor.0) {
+                if items.len() == limit {
+                    return Page {
+                        items,
+                        next_cursor: U64::from(next_cursor),
+                        has_more: true,
+                    };
+                }
+                items.push(id);
+                next_cursor = id.0;
+            }
+            let bucket_end = (index + 1).saturating_mul(bucket_size) - 1;
+            next_cursor = next_cursor.max(bucket_end.min(last_id));
+        }
+        Page {
+            items,
+            next_cursor: U64::from(next_cursor),
+            has_more: next_cursor < last_id,
+        }
+    }
+}
+
+/// Active source as recorded in a `RegistryManifest`
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct ManifestSource {
+    /// Id of the source in the exporting registry
+    pub id: U64,
+    /// `None` for sources registered before ownership was recorded
+    pub owner: Option<Address>,
+    pub paused: bool,
+    pub event_source: EventSource,
+}
+
+/// Snapshot of a registry's configuration, used to clone it into another
+/// registry
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct RegistryManifest {
+    pub version: u32,
+    pub chains: Vec<ChainInfo>,
+    pub admins: Vec<Address>,
+    /// Active sources in ascending id order
+    pub sources: Vec<ManifestSource>,
+}
+
+/// Registry-wide settings, changed by registry admins
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct RegistrySettings {
+    /// Whether new sources can be registered
+    pub registration_enabled: bool,
+}
+
+impl Default for RegistrySettings {
+    fn default() -> Self {
+        Self { registration_enabled: true }
+    }
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub enum AdminAction {
+    AddAdmin(Address),
+    RemoveAdmin(Address),
+    TransferOwnership(Address),
+    /// Removal of the source registered at the given index
+    ForceRemoveSource(U64),
+    UpdateSettings(RegistrySettings),
+    AddChain(ChainInfo),
+    UpdateChain(ChainInfo),
+    /// Import of a manifest with the given number of sources
+    ImportManifest(U64),
+    /// Compaction of the op log up to the given index
+    Compact(U64),
+    /// Change of the deposit required to register a source
+    SetSourceDeposit(U128),
+    WithdrawFees {
+        to: Address,
+        amount: U128,
+    },
+    /// Removal of the source registered at the given index, keeping its
+    /// deposit
+    SlashSource(U64),
+}
+
+/// Admin change recorded in the op log, sharing its index sequence with
+/// `EventSourceOp` entries
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct AdminOp {
+    pub caller: Address,
+    pub action: AdminAction,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub enum RegistryError {
+    /// `flow_contract_address` isn't a valid L1X address
+    InvalidFlowAddress(String),
+    /// The flow contract couldn't be asked for its admins
+    FlowAdminQueryFailed(String),
+    /// The caller isn't an admin of the flow contract
+    Unauthorized { caller: Address, flow_contract_address: Address },
+    /// The caller isn't the registry owner
+    NotOwner(Address),
+    /// The caller isn't a registry admin
+    NotAdmin(Address),
+    /// The address is already a registry admin
+    AlreadyAdmin(Address),
+    /// The address isn't a registry admin
+    UnknownAdmin(Address),
+    /// No source op is stored at the index
+    UnknownSource(U64),
+    /// The source has already been removed
+    AlreadyRemoved(U64),
+    /// The op at the index isn't the `Create` op of a source
+    NotACreateOp(U64),
+    /// Registry admins have disabled registration
+    RegistrationDisabled,
+    /// The caller neither owns the source nor is a registry admin
+    NotSourceOwner { caller: Address, index: U64 },
+    /// The update doesn't change the source
+    EmptyUpdate(U64),
+    /// An active source with the same identity exists
+    DuplicateSource(U64),
+    /// The source id is empty
+    InvalidSourceId(String),
+    /// Another active source of the flow uses the source id
+    DuplicateSourceId { source_id: String, id: U64 },
+    /// `chain` isn't in the chain catalog
+    UnknownChain(ChainId),
+    /// Registration on the chain is disabled in the chain catalog
+    ChainDisabled(ChainId),
+    /// The chain is already in the chain catalog
+    ChainAlreadyExists(ChainId),
+    /// The chain catalog entry is malformed
+    InvalidChain(ChainId),
+    /// `source_type` isn't a supported source kind
+    UnsupportedSourceType(SourceType),
+    /// The source kind can't be watched on the chain
+    IncompatibleSourceType { source_type: SourceType, chain: ChainId },
+    /// `smart_contract_address` isn't a `0x`-prefixed 20-byte hex address
+    InvalidContractAddress(String),
+    /// An event filter isn't a `0x`-prefixed 32-byte hex topic
+    InvalidEventFilter(String),
+    /// The topic filter is malformed
+    InvalidTopicFilter(String),
+    /// Listeners can't apply the finality policy on the chain
+    UnsupportedFinality { finality: FinalityPolicy, chain: ChainId },
+    /// `event_type` isn't a valid Solidity event signature
+    InvalidEventSignature(String),
+    /// `from_block` is past `to_block`
+    InvalidBlockRange { from_block: U64, to_block: U64 },
+    /// The source is already paused
+    AlreadyPaused(U64),
+    /// The source isn't paused
+    NotPaused(U64),
+    /// The manifest format version isn't supported
+    UnsupportedManifestVersion(u32),
+    /// Manifests can only be imported into a registry without active
+    /// sources
+    RegistryNotEmpty,
+    /// The op log is already compacted up to the given index
+    AlreadyCompacted(U64),
+    /// The registry is already stored with the given, latest, storage
+    /// version
+    StorageUpToDate(u32),
+    /// A native token transfer of the given amount failed
+    TransferFailed(U128),
+    /// More fees were requested than the registry holds
+    InsufficientFees { requested: U128, available: U128 },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidFlowAddress(error) => {
+                write!(f, "Invalid flow contract address: {}", error)
+            }
+            Self::FlowAdminQueryFailed(error) => {
+                write!(f, "Failed to query flow contract admins: {}", error)
+            }
+            Self::Unauthorized { caller, flow_contract_address } => write!(
+                f,
+                "Caller {} is not an admin of flow contract {}",
+                caller, flow_contract_address
+            ),
+            Self::NotOwner(caller) => {
+                write!(f, "Caller {} is not the registry owner", caller)
+            }
+            Self::NotAdmin(caller) => {
+                write!(f, "Caller {} is not a registry admin", caller)
+            }
+            Self::AlreadyAdmin(address) => {
+                write!(f, "{} is already a registry admin", address)
+            }
+            Self::UnknownAdmin(address) => {
+                write!(f, "{} is not a registry admin", address)
+            }
+            Self::UnknownSource(index) => {
+                write!(f, "No source at index {}", index.0)
+            }
+            Self::AlreadyRemoved(id) => {
+                write!(f, "Source {} is already removed", id.0)
+            }
+            Self::NotACreateOp(index) => write!(
+                f,
+                "Op at index {} doesn't create a source, use the source id",
+                index.0
+            ),
+            Self::RegistrationDisabled => {
+                write!(f, "Source registration is disabled")
+            }
+            Self::EmptyUpdate(id) => {
+                write!(f, "Update leaves source {} unchanged", id.0)
+            }
+            Self::DuplicateSource(id) => {
+                write!(f, "Source duplicates active source {}", id.0)
+            }
+            Self::InvalidSourceId(source_id) => {
+                write!(f, "Invalid source id: {:?}", source_id)
+            }
+            Self::DuplicateSourceId { source_id, id } => write!(
+                f,
+                "Source id {} is used by active source {} of the flow",
+                source_id, id.0
+            ),
+            Self::UnknownChain(chain) => write!(f, "Unknown chain: {}", chain),
+            Self::ChainDisabled(chain) => {
+                write!(f, "Chain {} is disabled", chain)
+            }
+            Self::ChainAlreadyExists(chain) => {
+                write!(f, "Chain {} already exists", chain)
+            }
+            Self::InvalidChain(chain) => {
+                write!(f, "Invalid chain catalog entry: {:?}", chain)
+            }
+            Self::UnsupportedSourceType(source_type) => {
+                write!(f, "Unsupported source type: {}", source_type)
+            }
+            Self::IncompatibleSourceType { source_type, chain } => write!(
+                f,
+                "Source type {} is not supported on chain {}",
+                source_type, chain
+            ),
+            Self::InvalidContractAddress(address) => {
+                write!(f, "Invalid smart contract address: {}", address)
+            }
+            Self::InvalidEventFilter(filter) => {
+                write!(f, "Invalid event filter: {}", filter)
+            }
+            Self::InvalidTopicFilter(error) => {
+                write!(f, "Invalid topic filter: {}", error)
+            }
+            Self::UnsupportedFinality { finality, chain } => write!(
+                f,
+                "Finality policy {:?} is not supported on chain {}",
+                finality, chain
+            ),
+            Self::InvalidEventSignature(error) => {
+                write!(f, "Invalid event signature: {}", error)
+            }
+            Self::InvalidBlockRange { from_block, to_block } => write!(
+                f,
+                "Invalid block range: {} is past {}",
+                from_block.0, to_block.0
+            ),
+            Self::AlreadyPaused(id) => {
+                write!(f, "Source {} is already paused", id.0)
+            }
+            Self::NotPaused(id) => write!(f, "Source {} is not paused", id.0),
+            Self::UnsupportedManifestVersion(version) => {
+                write!(f, "Unsupported manifest version: {}", version)
+            }
+            Self::RegistryNotEmpty => {
+                write!(f, "Registry already has active sources")
+            }
+            Self::AlreadyCompacted(index) => {
+                write!(f, "Op log is already compacted up to {}", index.0)
+            }
+            Self::StorageUpToDate(version) => {
+                write!(f, "Storage is already at version {}", version)
+            }
+            Self::TransferFailed(amount) => {
+                write!(f, "Transfer of {} native tokens failed", amount.0)
+            }
+            Self::InsufficientFees { requested, available } => write!(
+                f,
+                "Requested {} in fees, only {} available",
+                requested.0, available.0
+            ),
+            Self::NotSourceOwner { caller, index } => write!(
+                f,
+                "Caller {} is not the owner of source {}",
+                caller, index.0
+            ),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct SourceRegistry {
+    pub sources: LookupMap<U64, EventSourceOp>,
+    pub admin_ops: LookupMap<U64, AdminOp>,
+    /// Registering caller of each source, keyed by its `Create` op index
+    pub source_owners: LookupMap<U64, Address>,
+    /// Live view of the op log: sources that were created and not removed
+    pub active_sources: LookupMap<U64, ActiveSource>,
+    /// Ids of `active_sources` in ascending order, bucketed by
+    /// `id / ACTIVE_BUCKET_SIZE`
+    pub active_buckets: LookupMap<U64, Vec<U64>>,
+    /// Number of `active_sources`
+    pub active_count: U64,
+    /// Id of the active source with a given `EventSource::identity`
+    pub source_identities: LookupMap<String, U64>,
+    /// Ids of the active sources sharing an `EventSource::lookup_key`
+    pub source_lookup: LookupMap<String, Vec<U64>>,
+    /// Ids of the active sources of each flow, in ascending order, keyed by
+    /// `EventSource::flow_key`
+    pub flow_sources: LookupMap<String, Vec<U64>>,
+    /// Ids of the active sources on each chain, in ascending order, split
+    /// in buckets of `ACTIVE_BUCKET_SIZE` ids keyed by `chain_bucket_key`
+    pub chain_sources: LookupMap<String, Vec<U64>>,
+    /// Number of source ops recorded for each chain, bumped whenever the
+    /// chain's source set changes
+    pub chain_versions: LookupMap<String, U64>,
+    /// Id of the active source using a source id within its flow, keyed by
+    /// `EventSource::source_id_key`
+    pub source_ids: LookupMap<String, U64>,
+    pub index: U64,
+    /// Op log indices up to this one have been compacted: only the `Create`
+    /// ops of sources that were active then are kept
+    pub compacted_to: U64,
+    pub owner: Address,
+    pub admins: Vec<Address>,
+    pub settings: RegistrySettings,
+    /// Chain catalog, keyed by chain name
+    pub chains: LookupMap<ChainId, ChainInfo>,
+    /// Names of `chains` in insertion order
+    pub chain_names: Vec<ChainId>,
+    /// Deposit in native tokens required to register a source
+    pub source_deposit: U128,
+    /// Deposit held for each active source, keyed by its id
+    pub deposits: LookupMap<U64, SourceDeposit>,
+    /// Deposits of slashed sources, withdrawable by the owner
+    pub fees: U128,
+}
+
+#[contract]
+impl SourceRegistry {
+    /// Instantiate and save contract to storage
+    ///
+    /// The caller becomes the registry owner
+    pub fn new() {
+        let mut contract = Self::empty(l1x_sdk::caller_address());
+        contract.save();
+    }
+
+    /// Registry without sources, with the default chain catalog
+    fn empty(owner: Address) -> Self {
+        let mut contract = SourceRegistry {
+            sources: LookupMap::new(REGISTRY_SOURCES.to_vec()),
+            admin_ops: LookupMap::new(REGISTRY_ADMIN_OPS.to_vec()),
+            source_owners: LookupMap::new(REGISTRY_SOURCE_OWNERS.to_vec()),
+            active_sources: LookupMap::new(REGISTRY_ACTIVE_SOURCES.to_vec()),
+            active_buckets: LookupMap::new(REGISTRY_ACTIVE_IDS.to_vec()),
+            active_count: U64::from(0),
+            source_identities: LookupMap::new(
+                REGISTRY_SOURCE_IDENTITIES.to_vec(),
+            ),
+            source_lookup: LookupMap::new(REGISTRY_SOURCE_LOOKUP.to_vec()),
+            flow_sources: LookupMap::new(REGISTRY_FLOW_SOURCES.to_vec()),
+            chain_sources: LookupMap::new(REGISTRY_CHAIN_SOURCES.to_vec()),
+            chain_versions: LookupMap::new(REGISTRY_CHAIN_VERSIONS.to_vec()),
+            source_ids: LookupMap::new(REGISTRY_SOURCE_IDS.to_vec()),
+            index: U64::from(0),
+            compacted_to: U64::from(0),
+            owner,
+            admins: vec![],
+            settings: RegistrySettings::default(),
+            chains: LookupMap::new(REGISTRY_CHAINS.to_vec()),
+            chain_names: vec![],
+            source_deposit: U128::from(0),
+            deposits: LookupMap::new(REGISTRY_DEPOSITS.to_vec()),
+            fees: U128::from(0),
+        };
+        for chain in ChainInfo::defaults() {
+            contract.chain_names.push(chain.name.clone());
+            contract.chains.set(chain.name.clone(), Some(chain));
+        }
+        contract
+    }
+
+    fn load() -> Self {
+        let bytes = match l1x_sdk::storage_read(STORAGE_STATE_KEY) {
+            Some(bytes) => bytes,
+            None if l1x_sdk::storage_read(STORAGE_CONTRACT_KEY).is_some() => {
+                panic!("The contract storage is outdated, call migrate")
+            }
+            None => panic!("The contract isn't initialized"),
+        };
+        let mut buf = bytes.as_slice();
+        let version = <u32 as BorshDeserialize>::deserialize(&mut buf).unwrap();
+        if version < STORAGE_VERSION {
+            panic!("The contract storage is outdated, call migrate");
+        }
+        if version != STORAGE_VERSION {
+            panic!("Unsupported storage version: {}", version);
+        }
+        Self::try_from_slice(buf).unwrap()
+    }
+
+    fn save(&mut self) {
+        let mut encoded_contract = STORAGE_VERSION.try_to_vec().unwrap();
+        encoded_contract
+            .extend(borsh::BorshSerialize::try_to_vec(self).unwrap());
+        l1x_sdk::storage_write(STORAGE_STATE_KEY, &encoded_contract);
+    }
+
+    /// Storage version of the stored registry, `STORAGE_VERSION` once
+    /// migrated
+    pub fn storage_version() -> u32 {
+        if let Some(bytes) = l1x_sdk::storage_read(STORAGE_STATE_KEY) {
+            return <u32 as BorshDeserialize>::deserialize(
+                &mut bytes.as_slice(),
+            )
+            .unwrap();
+        }
+        match l1x_sdk::storage_read(STORAGE_CONTRACT_KEY) {
+            Some(_) => 1,
+            None => panic!("The contract isn't initialized"),
+        }
+    }
+
+    /// Upgrade the stored registry to `STORAGE_VERSION`
+    ///
+    /// Version 1 registries have no owner or admins: the contract owner
+    /// migrates them and becomes the registry owner, and the default chain
+    /// catalog is installed. Every op of the log is rewritten in the current
+    /// layout under its index, without emitting events, and the active
+    /// sources view is rebuilt. Legacy sources are kept without validation,
+    /// duplicates included, and have no recorded owner. Ops are replayed
+    /// `MIGRATION_BATCH_SIZE` per call, so `migrate` has to be called until
+    /// it returns `STORAGE_VERSION`, and the registry can't be used before
+    ///
+    /// Returns the storage version after the call, still 1 while version 1
+    /// ops are left to replay
+    pub fn migrate() -> Result<u32, RegistryError> {
+        let version = Self::storage_version();
+        if version == STORAGE_VERSION {
+            return Err(RegistryError::StorageUpToDate(version));
+        }
+        let caller = l1x_sdk::caller_address();
+        let owner = l1x_sdk::contract_owner_address();
+        if caller != owner {
+            return Err(RegistryError::NotOwner(caller));
+        }
+        let Some(mut contract) = Self::from_v1(owner) else {
+            return Ok(version);
+        };
+
+        contract.save();
+        l1x_sdk::storage_remove(STORAGE_CONTRACT_KEY);
+        l1x_sdk::storage_remove(STORAGE_MIGRATION_KEY);
+        Ok(STORAGE_VERSION)
+    }
+
+    /// Replay the next batch of ops of the registry stored by storage
+    /// version 1 into the current layout
+    ///
+    /// Returns the rebuilt registry once the last op is replayed, `None`
+    /// while ops are left, with the progress saved for the next call
+    ///
+    /// - `owner`: Registry owner
+    fn from_v1(owner: Address) -> Option<Self> {
+        let bytes = l1x_sdk::storage_read(STORAGE_CONTRACT_KEY)
+            .expect("Version 1 storage is missing");
+        let legacy = SourceRegistryV1::try_from_slice(&bytes).unwrap();
+        let MigrationV1 { mut contract, next_index } =
+            match l1x_sdk::storage_read(STORAGE_MIGRATION_KEY) {
+                Some(bytes) => MigrationV1::try_from_slice(&bytes).unwrap(),
+                None => {
+                    let mut contract = Self::empty(owner);
+                    contract.index = legacy.index;
+                    MigrationV1 { contract, next_index: U64::from(1) }
+                }
+            };
+        let last_index = legacy
+            .index
+            .0
+            .min(next_index.0.saturating_add(MIGRATION_BATCH_SIZE - 1));
+        for index in next_index.0..=last_index {
+            let index = U64::from(index);
+            let Some(source_op) = legacy.sources.get(&index) else {
+                continue;
+            };
+            let chain = contract
+                .chains
+                .get(&ChainId::from(source_op.event_source.chain.as_str()));
+            let event_source = source_op.event_source.clone().upgrade(chain);
+            contract.bump_chain_version(&event_source.chain);
+
+            let id = match source_op.op {
+                OperationV1::Create => {
+                    contract.index_source(index, &event_source);
+                    contract.active_sources.set(
+                        index,
+                        Some(ActiveSource {
+                            id: index,
+                            event_source: event_source.clone(),
+                            paused: false,
+                        }),
+                    );
+                    contract.add_active_id(index);
+                    index
+                }
+                // Version 1 removals copy the removed `Create` op
+                OperationV1::Remove => {
+                    let removed_id = contract
+                        .source_lookup
+                        .get(&event_source.source_lookup_key())
+                        .and_then(|ids| {
+                            ids.iter().copied().find(|id| {
+                                contract.active_sources.get(id).is_some_and(
+                                    |active_source| {
+                                        active_source.event_source
+                                            == event_source
+                                    },
+                                )
+                            })
+                        })
+                        .unwrap_or(index);
+                    if contract.active_sources.remove(&removed_id).is_some() {
+                        contract.remove_active_id(removed_id);
+                        contract.unindex_source(removed_id, &event_source);
+                    }
+                    removed_id
+                }
+            };
+            let op = match source_op.op {
+                OperationV1::Create => Operation::Create,
+                OperationV1::Remove => Operation::Remove,
+            };
+            contract
+                .sources
+                .set(index, Some(EventSourceOp { id, event_source, op }));
+        }
+
+        if last_index < legacy.index.0 {
+            let progress =
+                MigrationV1 { contract, next_index: U64::from(last_index + 1) };
+            l1x_sdk::storage_write(
+                STORAGE_MIGRATION_KEY,
+                &progress.try_to_vec().unwrap(),
+            );
+            return None;
+        }
+        Some(contract)
+    }
+
+    /// Allocate the next op log index
+    fn next_index(&mut self) -> U64 {
+        self.index.0 += 1;
+        self.index
+    }
+
+    /// Whether `address` can moderate the registry. The owner always can
+    fn is_admin(&self, address: &Address) -> bool {
+        self.owner == *address || self.admins.contains(address)
+    }
+
+    fn ensure_owner(&self) -> Result<Address, RegistryError> {
+        let caller = l1x_sdk::caller_address();
+        if self.owner != caller {
+            return Err(RegistryError::NotOwner(caller));
+        }
+        Ok(caller)
+    }
+
+    fn ensure_admin(&self) -> Result<Address, RegistryError> {
+        let caller = l1x_sdk::caller_address();
+        if !self.is_admin(&caller) {
+            return Err(RegistryError::NotAdmin(caller));
+        }
+        Ok(caller)
+    }
+
+    /// Append an admin change to the op log
+    fn record_admin_op(&mut self, caller: Address, action: AdminAction) -> U64 {
+        let index = self.next_index();
+        let admin_op = AdminOp { caller, action };
+        self.admin_ops.set(index, Some(admin_op.clone()));
+        l1x_sdk::emit_event_experimental(RegistryEvent::AdminChanged {
+            index,
+            admin_op,
+        });
+        index
+    }
+
+    /// Verify that the caller owns the source at `index` or is a registry
+    /// admin
+    fn ensure_source_owner(&self, index: U64) -> Result<(), RegistryError> {
+        let caller = l1x_sdk::caller_address();
+        if self.source_owners.get(&index) == Some(&caller)
+            || self.is_admin(&caller)
+        {
+            return Ok(());
+        }
+        Err(RegistryError::NotSourceOwner { caller, index })
+    }
+
+    /// Fail unless `id` is the id of an active source, telling why
+    ///
+    /// Ops dropped by `compact` belonged to removed sources or superseded
+    /// their active sources, so ids up to `compacted_to` no longer in the
+    /// log are reported as removed
+    fn ensure_active(&self, id: U64) -> Result<(), RegistryError> {
+        if self.active_sources.contains_key(&id) {
+            return Ok(());
+        }
+        if self.admin_ops.contains_key(&id) {
+            return Err(RegistryError::NotACreateOp(id));
+        }
+        match self.sources.get(&id) {
+            Some(source_op) if source_op.op != Operation::Create => {
+                Err(RegistryError::NotACreateOp(id))
+            }
+            Some(_) => Err(RegistryError::AlreadyRemoved(id)),
+            None if id <= self.compacted_to => {
+                Err(RegistryError::AlreadyRemoved(id))
+            }
+            None => Err(RegistryError::UnknownSource(id)),
+        }
+    }
+
+    /// Append a `Remove` op for the active source `id`
+    fn remove_source(&mut self, id: U64) -> Result<U64, RegistryError> {
+        self.ensure_active(id)?;
+        let active_source = self
+            .active_sources
+            .remove(&id)
+            .ok_or(RegistryError::UnknownSource(id))?;
+        self.remove_active_id(id);
+        self.unindex_source(id, &active_source.event_source);
+        Ok(self.append_source_op(
+            id,
+            active_source.event_source,
+            Operation::Remove,
+        ))
+    }
+
+    /// Fail if another active source has the same identity as
+    /// `event_source`, or the same source id within its flow
+    fn ensure_unique(
+        &self,
+        id: U64,
+        event_source: &EventSource,
+    ) -> Result<(), RegistryError> {
+        if let Some(existing_id) =
+            self.source_identities.get(&event_source.identity())
+        {
+            if *existing_id != id {
+                return Err(RegistryError::DuplicateSource(*existing_id));
+            }
+        }
+        match self.source_ids.get(&event_source.flow_source_id_key()) {
+            Some(existing_id) if *existing_id != id => {
+                Err(RegistryError::DuplicateSourceId {
+                    source_id: event_source.source_id.clone(),
+                    id: *existing_id,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Add the active source `id` to the lookup indices
+    ///
+    /// Sources migrated from version 1 may duplicate each other, in which
+    /// case the identity and source id keys stay with the oldest one
+    fn index_source(&mut self, id: U64, event_source: &EventSource) {
+        let identity = event_source.identity();
+        if !self.source_identities.contains_key(&identity) {
+            self.source_identities.set(identity, Some(id));
+        }
+        let source_id_key = event_source.flow_source_id_key();
+        if !self.source_ids.contains_key(&source_id_key) {
+            self.source_ids.set(source_id_key, Some(id));
+        }
+        Self::insert_id(
+            &mut self.source_lookup,
+            event_source.source_lookup_key(),
+            id,
+        );
+        Self::insert_id(&mut self.flow_sources, event_source.flow_key(), id);
+        Self::insert_id(
+            &mut self.chain_sources,
+            Self::chain_bucket_key(
+                &event_source.chain,
+                id.0 / ACTIVE_BUCKET_SIZE,
+            ),
+            id,
+        );
+    }
+
+    /// Drop the source `id` from the lookup indices
+    ///
+    /// Identity and source id keys held by `id` move to the oldest
+    /// remaining duplicate, if any
+    fn unindex_source(&mut self, id: U64, event_source: &EventSource) {
+        Self::remove_id(
+            &mut self.source_lookup,
+            event_source.source_lookup_key(),
+            id,
+        );
+        Self::remove_id(&mut self.flow_sources, event_source.flow_key(), id);
+        Self::remove_id(
+            &mut self.chain_sources,
+            Self::chain_bucket_key(
+                &event_source.chain,
+                id.0 / ACTIVE_BUCKET_SIZE,
+            ),
+            id,
+        );
+
+        let identity = event_source.identity();
+        if self.source_identities.get(&identity) == Some(&id) {
+            let duplicate = self.find_duplicate(
+                self.source_lookup.get(&event_source.source_lookup_key()),
+                |other| other.identity() == identity,
+            );
+            self.source_identities.set(identity, duplicate);
+        }
+        let source_id_key = event_source.flow_source_id_key();
+        if self.source_ids.get(&source_id_key) == Some(&id) {
+            let duplicate = self.find_duplicate(
+                self.flow_sources.get(&event_source.flow_key()),
+                |other| other.flow_source_id_key() == source_id_key,
+            );
+            self.source_ids.set(source_id_key, duplicate);
+        }
+    }
+
+    /// First of the indexed `ids` whose active source matches `is_duplicate`
+    fn find_duplicate(
+        &self,
+        ids: Option<&Vec<U64>>,
+        is_duplicate: impl Fn(&EventSource) -> bool,
+    ) -> Option<U64> {
+        ids?.iter().copied().find(|id| {
+            self.active_sources
+                .get(id)
+                .is_some_and(|active| is_duplicate(&active.event_source))
+        })
+    }
+
+    /// Add `id` to the ids stored under `key`, keeping them sorted
+    fn insert_id(
+        index: &mut LookupMap<String, Vec<U64>>,
+        key: String,
+        id: U64,
+    ) {
+        let mut ids = index.get(&key).cloned().unwrap_or_default();
+        if let Err(position) = ids.binary_search(&id) {
+            ids.insert(position, id);
+        }
+        index.set(key, Some(ids));
+    }
+
+    /// Remove `id` from the ids stored under `key`, dropping the key once
+    /// no ids are left
+    fn remove_id(
+        index: &mut LookupMap<String, Vec<U64>>,
+        key: String,
+        id: U64,
+    ) {
+        let mut ids = index.get(&key).cloned().unwrap_or_default();
+        ids.retain(|source_id| *source_id != id);
+        index.set(key, if ids.is_empty() { None } else { Some(ids) });
+    }
+
+    fn chain_version(&self, chain: &ChainId) -> U64 {
+        self.chain_versions.get(&chain.0).copied().unwrap_or_default()
+    }
+
+    /// Add `id` to `active_buckets`
+    fn add_active_id(&mut self, id: U64) {
+        let bucket = U64::from(id.0 / ACTIVE_BUCKET_SIZE);
+        let mut ids =
+            self.active_buckets.get(&bucket).cloned().unwrap_or_default();
+        if let Err(position) = ids.binary_search(&id) {
+            ids.insert(position, id);
+            self.active_count.0 += 1;
+        }
+        self.active_buckets.set(bucket, Some(ids));
+    }
+
+    /// Remove `id` from `active_buckets`
+    fn remove_active_id(&mut self, id: U64) {
+        let bucket = U64::from(id.0 / ACTIVE_BUCKET_SIZE);
+        let mut ids =
+            self.active_buckets.get(&bucket).cloned().unwrap_or_default();
+        if let Ok(position) = ids.binary_search(&id) {
+            ids.remove(position);
+            self.active_count.0 -= 1;
+        }
+        self.active_buckets
+            .set(bucket, if ids.is_empty() { None } else { Some(ids) });
+    }
+
+    /// Ids of the active sources in `active_buckets` bucket `bucket`
+    fn active_bucket(&self, bucket: u64) -> Vec<U64> {
+        self.active_buckets.get(&U64::from(bucket)).cloned().unwrap_or_default()
+    }
+
+    /// Key of bucket `bucket` of a chain's ids in `chain_sources`
+    fn chain_bucket_key(chain: &ChainId, bucket: u64) -> String {
+        format!("{}:{}", chain.0, bucket)
+    }
+
+    /// Ids of the active sources on `chain` in bucket `bucket`
+    fn chain_bucket(&self, chain: &ChainId, bucket: u64) -> Vec<U64> {
+        self.chain_sources
+            .get(&Self::chain_bucket_key(chain, bucket))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Resolve a page of active source ids to the sources
+    fn resolve_page(&self, ids: Page<U64>) -> Page<ActiveSource> {
+        Page {
+            items: ids
+                .items
+                .iter()
+                .filter_map(|id| self.active_sources.get(id).cloned())
+                .collect(),
+            next_cursor: ids.next_cursor,
+            has_more: ids.has_more,
+        }
+    }
+
+    /// Page through the active sources among the ascending `ids`
+    fn active_sources_page(
+        &self,
+        ids: &[U64],
+        cursor: U64,
+        limit: u32,
+    ) -> Page<ActiveSource> {
+        let start = ids.partition_point(|id| id.0 <= cursor.0);
+        let end = ids.len().min(start.saturating_add(Self::page_limit(limit)));
+
+        let items: Vec<ActiveSource> = ids[start..end]
+            .iter()
+            .filter_map(|id| self.active_sources.get(id).cloned())
+            .collect();
+        let next_cursor = if end > start { ids[end - 1] } else { cursor };
+        Page { items, next_cursor, has_more: end < ids.len() }
+    }
+
+    /// Fill in derived fields of `event_source`, then check it against the
+    /// chain catalog and its own rules
+    fn prepare_source(
+        &self,
+        event_source: &mut EventSource,
+    ) -> Result<(), RegistryError> {
+        let chain = self.enabled_chain(&event_source.chain)?;
+        event_source.derive_topic0(chain)?;
+        event_source.validate(chain)
+    }
+
+    /// Build the source to store from the caller's input, filling the
+    /// chain's default finality in and checking the result
+    fn prepare_new_source(
+        &self,
+        new_source: NewEventSource,
+    ) -> Result<EventSource, RegistryError> {
+        let finality = match new_source.finality {
+            Some(finality) => finality,
+            None => self.enabled_chain(&new_source.chain)?.default_finality(),
+        };
+        let mut event_source = EventSource {
+            flow_contract_address: new_source.flow_contract_address,
+            source_id: new_source.source_id,
+            chain: new_source.chain,
+            source_type: new_source.source_type,
+            smart_contract_address: new_source.smart_contract_address,
+            event_type: new_source.event_type,
+            event_filters: new_source.event_filters,
+            from_block: new_source.from_block,
+            to_block: new_source.to_block,
+            finality,
+            topic0: None,
+        };
+        self.prepare_source(&mut event_source)?;
+        Ok(event_source)
+    }
+
+    /// Retrieve the catalog entry of a chain sources can be registered on
+    fn enabled_chain(
+        &self,
+        chain: &ChainId,
+    ) -> Result<&ChainInfo, RegistryError> {
+        let chain = self
+            .chains
+            .get(chain)
+            .ok_or_else(|| RegistryError::UnknownChain(chain.clone()))?;
+        if !chain.enabled {
+            return Err(RegistryError::ChainDisabled(chain.name.clone()));
+        }
+        Ok(chain)
+    }
+
+    /// Record a change to the source set of `chain`
+    fn bump_chain_version(&mut self, chain: &ChainId) {
+        let version = self.chain_version(chain);
+        self.chain_versions
+            .set(chain.0.clone(), Some(U64::from(version.0 + 1)));
+    }
+
+    /// Append an op affecting the source `id` to the op log
+    fn append_source_op(
+        &mut self,
+        id: U64,
+        event_source: EventSource,
+        op: Operation,
+    ) -> U64 {
+        self.bump_chain_version(&event_source.chain);
+        let index = self.next_index();
+        let source_op = EventSourceOp { id, event_source, op };
+        self.sources.set(index, Some(source_op.clone()));
+
+        let indexed_source_op = IndexedSourceOp { index, source_op };
+        let event = match indexed_source_op.source_op.op {
+            Operation::Create => {
+                RegistryEvent::SourceRegistered(indexed_source_op)
+            }
+            Operation::Remove => {
+                RegistryEvent::SourceRemoved(indexed_source_op)
+            }
+            Operation::Update(_)
+            | Operation::Pause
+            | Operation::Resume
+            | Operation::TransferOwnership(_) => {
+                RegistryEvent::SourceUpdated(indexed_source_op)
+            }
+        };
+        l1x_sdk::emit_event_experimental(event);
+        index
+    }
+
+    /// Flip the paused flag of an active source, appending `Pause` or
+    /// `Resume` to the op log
+    fn set_source_paused(id: U64, paused: bool) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        contract.ensure_active(id)?;
+        contract.ensure_source_owner(id)?;
+        let mut active_source = contract
+            .active_sources
+            .get(&id)
+            .cloned()
+            .ok_or(RegistryError::UnknownSource(id))?;
+        if active_source.paused == paused {
+            return Err(if paused {
+                RegistryError::AlreadyPaused(id)
+            } else {
+                RegistryError::NotPaused(id)
+            });
+        }
+
+        active_source.paused = paused;
+        let op = if paused { Operation::Pause } else { Operation::Resume };
+        let index = contract.append_source_op(
+            id,
+            active_source.event_source.clone(),
+            op,
+        );
+        contract.active_sources.set(id, Some(active_source));
+        contract.save();
+        Ok(index)
+    }
+
+    /// Clamp a caller-provided page size to `1..=MAX_PAGE_SIZE`
+    fn page_limit(limit: u32) -> usize {
+        limit.clamp(1, MAX_PAGE_SIZE) as usize
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_new_source(
+        flow_contract_address: String,
+        source_id: String,
+        chain: ChainId,
+        source_type: SourceType,
+        smart_contract_address: String,
+        event_type: String,
+        event_filters: TopicFilter,
+        from_block: Option<U64>,
+        to_block: Option<U64>,
+        finality: Option<FinalityPolicy>,
+    ) -> Result<RegisteredSource, RegistryError> {
+        let mut contract = Self::load();
+        if !contract.settings.registration_enabled {
+            return Err(RegistryError::RegistrationDisabled);
+        }
+        let new_source = contract.prepare_new_source(NewEventSource {
+            flow_contract_address,
+            source_id,
+            chain,
+            source_type,
+            smart_contract_address,
+            event_type,
+            event_filters,
+            from_block,
+            to_block,
+            finality,
+        })?;
+        Self::ensure_flow_admin(&new_source.flow_contract_address)?;
+
+        contract.ensure_unique(U64::from(contract.index.0 + 1), &new_source)?;
+        let deposit = contract.collect_deposits(1)?;
+        let event_signature = new_source.event_signature();
+        let id = contract.insert_source(new_source);
+        contract.hold_deposit(id, deposit);
+        contract.save();
+        Ok(RegisteredSource { id, event_signature })
+    }
+
+    /// Register several sources at once, under consecutive indices
+    ///
+    /// Every source is checked before any is registered, so either all of
+    /// them are registered or none is. The source deposit is due for each
+    /// source
+    ///
+    /// - `event_sources`: Sources to register
+    pub fn register_sources_batch(
+        event_sources: Vec<NewEventSource>,
+    ) -> Result<Vec<RegisteredSource>, RegistryError> {
+        let mut contract = Self::load();
+        if !contract.settings.registration_enabled {
+            return Err(RegistryError::RegistrationDisabled);
+        }
+
+        let first_id = contract.index.0 + 1;
+        let mut identities: HashMap<String, U64> = HashMap::new();
+        let mut source_ids: HashMap<String, U64> = HashMap::new();
+        let mut flows: Vec<String> = vec![];
+        let mut new_sources = Vec::with_capacity(event_sources.len());
+        for (offset, new_source) in event_sources.into_iter().enumerate() {
+            let id = U64::from(first_id + offset as u64);
+            let new_source = contract.prepare_new_source(new_source)?;
+            contract.ensure_unique(id, &new_source)?;
+            // Sources of the batch must not duplicate each other either
+            if let Some(existing_id) =
+                identities.insert(new_source.identity(), id)
+            {
+                return Err(RegistryError::DuplicateSource(existing_id));
+            }
+            if let Some(existing_id) =
+                source_ids.insert(new_source.flow_source_id_key(), id)
+            {
+                return Err(RegistryError::DuplicateSourceId {
+                    source_id: new_source.source_id,
+                    id: existing_id,
+                });
+            }
+            if !flows.contains(&new_source.flow_key()) {
+                Self::ensure_flow_admin(&new_source.flow_contract_address)?;
+                flows.push(new_source.flow_key());
+            }
+            new_sources.push(new_source);
+        }
+
+        let deposit = contract.collect_deposits(new_sources.len())?;
+        let registered = new_sources
+            .into_iter()
+            .map(|new_source| {
+                let event_signature = new_source.event_signature();
+                let id = contract.insert_source(new_source);
+                contract.hold_deposit(id, deposit);
+                RegisteredSource { id, event_signature }
+            })
+            .collect();
+        contract.save();
+        Ok(registered)
+    }
+
+    /// Collect the source deposit of `count` sources from the caller
+    ///
+    /// Returns the deposit to hold for each source
+    fn collect_deposits(&self, count: usize) -> Result<U128, RegistryError> {
+        let total = self.source_deposit.0.saturating_mul(count as u128);
+        if total > 0 && !l1x_sdk::transfer_from_caller(total) {
+            return Err(RegistryError::TransferFailed(U128::from(total)));
+        }
+        Ok(self.source_deposit)
+    }
+
+    /// Hold `deposit`, paid by the caller, for the new source `id`
+    fn hold_deposit(&mut self, id: U64, deposit: U128) {
+        if deposit.0 > 0 {
+            let depositor = l1x_sdk::caller_address();
+            self.deposits
+                .set(id, Some(SourceDeposit { depositor, amount: deposit }));
+        }
+    }
+
+    /// Refund the deposit held for the source `id` to its depositor
+    ///
+    /// Called before the source is removed, so that a failed transfer
+    /// leaves the source and its deposit untouched
+    fn refund_deposit(&mut self, id: U64) -> Result<(), RegistryError> {
+        let Some(deposit) = self.deposits.get(&id).copied() else {
+            return Ok(());
+        };
+        if !l1x_sdk::transfer_to(&deposit.depositor, deposit.amount.0) {
+            return Err(RegistryError::TransferFailed(deposit.amount));
+        }
+        self.deposits.remove(&id);
+        Ok(())
+    }
+
+    /// Move the deposit held for the removed source `id` to the fees
+    fn forfeit_deposit(&mut self, id: U64) {
+        if let Some(deposit) = self.deposits.remove(&id) {
+            self.fees.0 += deposit.amount.0;
+        }
+    }
+
+    /// Record a checked source with the caller as owner
+    ///
+    /// Returns the id of the source, which is the index of its `Create` op
+    fn insert_source(&mut self, new_source: EventSource) -> U64 {
+        let id = U64::from(self.index.0 + 1);
+        self.index_source(id, &new_source);
+        self.append_source_op(id, new_source.clone(), Operation::Create);
+        self.active_sources.set(
+            id,
+            Some(ActiveSource { id, event_source: new_source, paused: false }),
+        );
+        self.add_active_id(id);
+        self.source_owners.set(id, Some(l1x_sdk::caller_address()));
+        id
+    }
+
+    /// Retrieve admins of the flow contract at `flow_contract_address`
+    fn flow_contract_admins(
+        flow_contract_address: Address,
+    ) -> Result<Vec<Address>, RegistryError> {
+        let call = ContractCall {
+            contract_address: flow_contract_address,
+            method_name: "get_contract_admins".to_string(),
+            args: vec![],
+            read_only: true,
+            fee_limit: 0,
+        };
+        let res = l1x_sdk::call_contract(&call).ok_or_else(|| {
+            RegistryError::FlowAdminQueryFailed(
+                "Failed to call contract".to_string(),
+            )
+        })?;
+        serde_json::from_slice::<Vec<Address>>(&res).map_err(|error| {
+            RegistryError::FlowAdminQueryFailed(error.to_string())
+        })
+    }
+
+    /// Verify that the caller is allowed to register sources for the flow
+    ///
+    /// - `flow_contract_address`: Address of the flow contract
+    fn ensure_flow_admin(
+        flow_contract_address: &str,
+    ) -> Result<(), RegistryError> {
+        let flow_contract_address =
+            Address::try_from(flow_contract_address.to_string()).map_err(
+                |error| RegistryError::InvalidFlowAddress(error.to_string()),
+            )?;
+        let authorized_addresses =
+            Self::flow_contract_admins(flow_contract_address)?;
+
+        let caller = l1x_sdk::caller_address();
+        if !authorized_addresses.contains(&caller) {
+            return Err(RegistryError::Unauthorized {
+                caller,
+                flow_contract_address,
+            });
+        }
+        Ok(())
+    }
+
+    /// Remove a source. Source owner or registry admin only
+    ///
+    /// The deposit held for the source is refunded to its depositor.
+    /// Returns the index of the appended `Remove` op
+    ///
+    /// - `id`: Id of the source, i.e. the index of its `Create` op
+    pub fn unregister_source(id: U64) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        contract.ensure_active(id)?;
+        contract.ensure_source_owner(id)?;
+        contract.refund_deposit(id)?;
+        let remove_index = contract.remove_source(id)?;
+        contract.save();
+        Ok(remove_index)
+    }
+
+    /// Modify an active source in place, keeping its id. Source owner or
+    /// registry admin only
+    ///
+    /// The appended `Update` op carries only the fields that changed
+    ///
+    /// - `id`: Id of the source
+    /// - `update`: New values of the fields to change
+    pub fn update_source(
+        id: U64,
+        update: SourceUpdate,
+    ) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        contract.ensure_active(id)?;
+        contract.ensure_source_owner(id)?;
+        let mut active_source = contract
+            .active_sources
+            .get(&id)
+            .cloned()
+            .ok_or(RegistryError::UnknownSource(id))?;
+
+        let previous_source = active_source.event_source.clone();
+        update.apply(&mut active_source.event_source);
+        contract.prepare_source(&mut active_source.event_source)?;
+        let changes =
+            SourceUpdate::diff(&previous_source, &active_source.event_source);
+        if changes == SourceUpdate::default() {
+            return Err(RegistryError::EmptyUpdate(id));
+        }
+        contract.ensure_unique(id, &active_source.event_source)?;
+        contract.unindex_source(id, &previous_source);
+        contract.index_source(id, &active_source.event_source);
+
+        let index = contract.append_source_op(
+            id,
+            active_source.event_source.clone(),
+            Operation::Update(changes),
+        );
+        contract.active_sources.set(id, Some(active_source));
+        contract.save();
+        Ok(index)
+    }
+
+    /// Temporarily stop event delivery for a source, keeping its
+    /// registration. Source owner or registry admin only
+    ///
+    /// - `id`: Id of the source
+    pub fn pause_source(id: U64) -> Result<U64, RegistryError> {
+        Self::set_source_paused(id, true)
+    }
+
+    /// Restart event delivery for a paused source. Source owner or registry
+    /// admin only
+    ///
+    /// - `id`: Id of the source
+    pub fn resume_source(id: U64) -> Result<U64, RegistryError> {
+        Self::set_source_paused(id, false)
+    }
+
+    /// Hand an active source over to a new owner. Source owner or registry
+    /// admin only
+    ///
+    /// Returns the index of the appended `TransferOwnership` op
+    ///
+    /// - `index`: Index of the source's `Create` op
+    /// - `new_owner`: Address of the new owner
+    pub fn transfer_source_ownership(
+        index: U64,
+        new_owner: Address,
+    ) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        contract.ensure_active(index)?;
+        contract.ensure_source_owner(index)?;
+        contract.source_owners.set(index, Some(new_owner));
+        let event_source = contract
+            .active_sources
+            .get(&index)
+            .map(|active_source| active_source.event_source.clone())
+            .ok_or(RegistryError::UnknownSource(index))?;
+        let op_index = contract.append_source_op(
+            index,
+            event_source,
+            Operation::TransferOwnership(new_owner),
+        );
+        contract.save();
+        Ok(op_index)
+    }
+
+    pub fn get_source_owner(index: U64) -> Option<Address> {
+        let contract = Self::load();
+        contract.source_owners.get(&index).cloned()
+    }
+
+    /// Remove an abusive source on behalf of the registry admins
+    ///
+    /// The deposit held for the source is refunded to its depositor, see
+    /// `slash_source` to keep it
+    ///
+    /// - `id`: Id of the source
+    pub fn force_remove_source(id: U64) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_admin()?;
+        contract.ensure_active(id)?;
+        contract.refund_deposit(id)?;
+        let remove_index = contract.remove_source(id)?;
+        contract.record_admin_op(caller, AdminAction::ForceRemoveSource(id));
+        contract.save();
+        Ok(remove_index)
+    }
+
+    /// Remove an abusive source and keep its deposit in the registry fees.
+    /// Owner only
+    ///
+    /// Returns the index of the appended `Remove` op
+    ///
+    /// - `id`: Id of the source
+    pub fn slash_source(id: U64) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_owner()?;
+        let remove_index = contract.remove_source(id)?;
+        contract.forfeit_deposit(id);
+        contract.record_admin_op(caller, AdminAction::SlashSource(id));
+        contract.save();
+        Ok(remove_index)
+    }
+
+    /// Prune source ops superseded by the active sources view. Admin only
+    ///
+    /// Up to `up_to_index`, only the `Create` ops of active sources are
+    /// kept: ops of removed sources and the updates, pauses and resumes of
+    /// active ones are dropped. Clients whose cursor is in compacted history
+    /// are then asked by `get_sources_from` to resync
+    ///
+    /// Returns the index the op log was compacted up to
+    ///
+    /// - `up_to_index`: Last op log index to compact, clamped to the latest
+    ///   index
+    pub fn compact(up_to_index: U64) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_admin()?;
+        let up_to_index = U64::from(up_to_index.0.min(contract.index.0));
+        if up_to_index <= contract.compacted_to {
+            return Err(RegistryError::AlreadyCompacted(contract.compacted_to));
+        }
+
+        for index in contract.compacted_to.0 + 1..=up_to_index.0 {
+            let index = U64::from(index);
+            let superseded = match contract.sources.get(&index) {
+                Some(source_op) => {
+                    source_op.op != Operation::Create
+                        || !contract.active_sources.contains_key(&index)
+                }
+                None => false,
+            };
+            if superseded {
+                contract.sources.remove(&index);
+            }
+        }
+        contract.compacted_to = up_to_index;
+        contract.record_admin_op(caller, AdminAction::Compact(up_to_index));
+        contract.save();
+        Ok(up_to_index)
+    }
+
+    /// Grant registry admin rights. Owner only
+    ///
+    /// - `admin`: Address of the new admin
+    pub fn add_admin(admin: Address) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_owner()?;
+        if contract.admins.contains(&admin) {
+            return Err(RegistryError::AlreadyAdmin(admin));
+        }
+        contract.admins.push(admin);
+        let index =
+            contract.record_admin_op(caller, AdminAction::AddAdmin(admin));
+        contract.save();
+        Ok(index)
+    }
+
+    /// Revoke registry admin rights. Owner only
+    ///
+    /// - `admin`: Address of the admin to remove
+    pub fn remove_admin(admin: Address) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_owner()?;
+        if !contract.admins.contains(&admin) {
+            return Err(RegistryError::UnknownAdmin(admin));
+        }
+        contract.admins.retain(|address| *address != admin);
+        let index =
+            contract.record_admin_op(caller, AdminAction::RemoveAdmin(admin));
+        contract.save();
+        Ok(index)
+    }
+
+    /// Hand the registry over to a new owner. Owner only
+    ///
+    /// - `new_owner`: Address of the new owner
+    pub fn transfer_ownership(
+        new_owner: Address,
+    ) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_owner()?;
+        contract.owner = new_owner;
+        let index = contract
+            .record_admin_op(caller, AdminAction::TransferOwnership(new_owner));
+        contract.save();
+        Ok(index)
+    }
+
+    /// Change registry-wide settings. Admin only
+    ///
+    /// - `settings`: New settings
+    pub fn update_settings(
+        settings: RegistrySettings,
+    ) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_admin()?;
+        contract.settings = settings.clone();
+        let index = contract
+            .record_admin_op(caller, AdminAction::UpdateSettings(settings));
+        contract.save();
+        Ok(index)
+    }
+
+    /// Set the deposit required to register a source. Admin only
+    ///
+    /// Registered sources keep the deposit they were registered with
+    ///
+    /// - `amount`: Deposit in native tokens, `0` to register for free
+    pub fn set_source_deposit(amount: U128) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_admin()?;
+        contract.source_deposit = amount;
+        let index = contract
+            .record_admin_op(caller, AdminAction::SetSourceDeposit(amount));
+        contract.save();
+        Ok(index)
+    }
+
+    /// Transfer deposits of slashed sources out of the registry. Owner only
+    ///
+    /// - `to`: Recipient of the fees
+    /// - `amount`: Amount of native tokens to withdraw
+    pub fn withdraw_fees(
+        to: Address,
+        amount: U128,
+    ) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_owner()?;
+        if amount.0 > contract.fees.0 {
+            return Err(RegistryError::InsufficientFees {
+                requested: amount,
+                available: contract.fees,
+            });
+        }
+        if !l1x_sdk::transfer_to(&to, amount.0) {
+            return Err(RegistryError::TransferFailed(amount));
+        }
+        contract.fees.0 -= amount.0;
+        let index = contract
+            .record_admin_op(caller, AdminAction::WithdrawFees { to, amount });
+        contract.save();
+        Ok(index)
+    }
+
+    /// Add a chain to the catalog. Admin only
+    ///
+    /// - `chain`: Catalog entry of the new chain
+    pub fn add_chain(chain: ChainInfo) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_admin()?;
+        if chain.name.as_str().trim().is_empty() {
+            return Err(RegistryError::InvalidChain(chain.name));
+        }
+        if contract.chains.contains_key(&chain.name) {
+            return Err(RegistryError::ChainAlreadyExists(chain.name));
+        }
+        Self::ensure_parent_chain(&chain, |name| {
+            contract.chains.contains_key(name)
+        })?;
+        contract.chain_names.push(chain.name.clone());
+        contract.chains.set(chain.name.clone(), Some(chain.clone()));
+        let index =
+            contract.record_admin_op(caller, AdminAction::AddChain(chain));
+        contract.save();
+        Ok(index)
+    }
+
+    /// Fail unless the parent of a rollup is another cataloged chain
+    ///
+    /// - `is_cataloged`: Whether a chain is in the catalog
+    fn ensure_parent_chain(
+        chain: &ChainInfo,
+        is_cataloged: impl Fn(&ChainId) -> bool,
+    ) -> Result<(), RegistryError> {
+        match &chain.parent_chain {
+            Some(parent) if *parent == chain.name => {
+                Err(RegistryError::InvalidChain(chain.name.clone()))
+            }
+            Some(parent) if !is_cataloged(parent) => {
+                Err(RegistryError::UnknownChain(parent.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Replace the catalog entry of a chain, e.g. to disable it. Admin only
+    ///
+    /// Sources already registered on the chain are left as they are
+    ///
+    /// - `chain`: New catalog entry, matched by name
+    pub fn update_chain(chain: ChainInfo) -> Result<U64, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_admin()?;
+        if !contract.chains.contains_key(&chain.name) {
+            return Err(RegistryError::UnknownChain(chain.name));
+        }
+        Self::ensure_parent_chain(&chain, |name| {
+            contract.chains.contains_key(name)
+        })?;
+        contract.chains.set(chain.name.clone(), Some(chain.clone()));
+        let index =
+            contract.record_admin_op(caller, AdminAction::UpdateChain(chain));
+        contract.save();
+        Ok(index)
+    }
+
+    /// Export the chain catalog, admins and active sources as a manifest
+    pub fn export_manifest() -> RegistryManifest {
+        let contract = Self::load();
+        let chains = contract
+            .chain_names
+            .iter()
+            .filter_map(|name| contract.chains.get(name).cloned())
+            .collect();
+        let sources = (0..=contract.index.0 / ACTIVE_BUCKET_SIZE)
+            .flat_map(|bucket| contract.active_bucket(bucket))
+            .filter_map(|id| contract.active_sources.get(&id))
+            .map(|active_source| ManifestSource {
+                id: active_source.id,
+                owner: contract.source_owners.get(&active_source.id).copied(),
+                paused: active_source.paused,
+                event_source: active_source.event_source.clone(),
+            })
+            .collect();
+        RegistryManifest {
+            version: MANIFEST_VERSION,
+            chains,
+            admins: contract.admins.clone(),
+            sources,
+        }
+    }
+
+    /// Replay a manifest exported by `export_manifest` into a registry
+    /// without active sources. Owner only, as flow admins aren't consulted
+    ///
+    /// Chains of the manifest are added to the catalog or replace the
+    /// entries of the same name, and its admins are added. Sources are
+    /// registered under consecutive indices in manifest order, with their
+    /// owner and paused state but new ids, and the `Pause` ops of paused
+    /// sources follow all `Create` ops. Sources without an owner in the
+    /// manifest are owned by the registry owner. Sources on disabled chains
+    /// are kept and no deposit is held for imported sources. The whole
+    /// manifest is checked before anything is imported
+    ///
+    /// Returns the new ids of the sources, in manifest order
+    ///
+    /// - `manifest`: Manifest to import
+    pub fn import_manifest(
+        manifest: RegistryManifest,
+    ) -> Result<Vec<U64>, RegistryError> {
+        let mut contract = Self::load();
+        let caller = contract.ensure_owner()?;
+        if manifest.version != MANIFEST_VERSION {
+            return Err(RegistryError::UnsupportedManifestVersion(
+                manifest.version,
+            ));
+        }
+        if contract.active_count.0 > 0 {
+            return Err(RegistryError::RegistryNotEmpty);
+        }
+
+        let mut chains: HashMap<ChainId, &ChainInfo> = HashMap::new();
+        for chain in &manifest.chains {
+            if chain.name.as_str().trim().is_empty() {
+                return Err(RegistryError::InvalidChain(chain.name.clone()));
+            }
+            if chains.insert(chain.name.clone(), chain).is_some() {
+                return Err(RegistryError::ChainAlreadyExists(
+                    chain.name.clone(),
+                ));
+            }
+        }
+        for chain in &manifest.chains {
+            Self::ensure_parent_chain(chain, |name| {
+                chains.contains_key(name) || contract.chains.contains_key(name)
+            })?;
+        }
+
+        let first_id = contract.index.0 + 1;
+        let mut identities: HashMap<String, U64> = HashMap::new();
+        let mut source_ids: HashMap<String, U64> = HashMap::new();
+        let mut new_sources = Vec::with_capacity(manifest.sources.len());
+        for (offset, source) in manifest.sources.into_iter().enumerate() {
+            let id = U64::from(first_id + offset as u64);
+            let mut new_source = source.event_source;
+            let chain = match chains.get(&new_source.chain) {
+                Some(chain) => *chain,
+                None => {
+                    contract.chains.get(&new_source.chain).ok_or_else(|| {
+                        RegistryError::UnknownChain(new_source.chain.clone())
+                    })?
+                }
+            };
+            new_source.topic0 = None;
+            new_source.derive_topic0(chain)?;
+            new_source.validate(chain)?;
+            if let Some(existing_id) =
+                identities.insert(new_source.identity(), id)
+            {
+                return Err(RegistryError::DuplicateSource(existing_id));
+            }
+            if let Some(existing_id) =
+                source_ids.insert(new_source.flow_source_id_key(), id)
+            {
+                return Err(RegistryError::DuplicateSourceId {
+                    source_id: new_source.source_id,
+                    id: existing_id,
+                });
+            }
+            new_sources.push((new_source, source.owner, source.paused));
+        }
+
+        for chain in manifest.chains {
+            let action = if contract.chains.contains_key(&chain.name) {
+                AdminAction::UpdateChain(chain.clone())
+            } else {
+                contract.chain_names.push(chain.name.clone());
+                AdminAction::AddChain(chain.clone())
+            };
+            contract.chains.set(chain.name.clone(), Some(chain));
+            contract.record_admin_op(caller, action);
+        }
+        for admin in manifest.admins {
+            if !contract.is_admin(&admin) {
+                contract.admins.push(admin);
+                contract.record_admin_op(caller, AdminAction::AddAdmin(admin));
+            }
+        }
+
+        contract.record_admin_op(
+            caller,
+            AdminAction::ImportManifest(U64::from(new_sources.len() as u64)),
+        );
+        let mut ids = Vec::with_capacity(new_sources.len());
+        let mut paused_ids = vec![];
+        for (new_source, owner, paused) in new_sources {
+            let id = contract.insert_source(new_source);
+            if let Some(owner) = owner {
+                contract.source_owners.set(id, Some(owner));
+            }
+            if paused {
+                paused_ids.push(id);
+            }
+            ids.push(id);
+        }
+        for id in paused_ids {
+            let mut active_source = contract
+                .active_sources
+                .get(&id)
+                .cloned()
+                .expect("Imported source is active");
+            active_source.paused = true;
+            contract.append_source_op(
+                id,
+                active_source.event_source.clone(),
+                Operation::Pause,
+            );
+            contract.active_sources.set(id, Some(active_source));
+        }
+        contract.save();
+        Ok(ids)
+    }
+
+    pub fn list_chains() -> Vec<ChainInfo> {
+        let contract = Self::load();
+        contract
+            .chain_names
+            .iter()
+            .filter_map(|name| contract.chains.get(name).cloned())
+            .collect()
+    }
+
+    pub fn get_chain(name: ChainId) -> Option<ChainInfo> {
+        let contract = Self::load();
+        contract.chains.get(&name).cloned()
+    }
+
+    pub fn get_owner() -> Address {
+        let contract = Self::load();
+        contract.owner
+    }
+
+    pub fn get_admins() -> Vec<Address> {
+        let contract = Self::load();
+        contract.admins
+    }
+
+    pub fn get_settings() -> RegistrySettings {
+        let contract = Self::load();
+        contract.settings
+    }
+
+    pub fn get_source_deposit() -> U128 {
+        let contract = Self::load();
+        contract.source_deposit
+    }
+
+    /// Retrieve the deposit held for a source, if any
+    ///
+    /// - `id`: Id of the source
+    pub fn get_deposit(id: U64) -> Option<SourceDeposit> {
+        let contract = Self::load();
+        contract.deposits.get(&id).copied()
+    }
+
+    pub fn get_fees() -> U128 {
+        let contract = Self::load();
+        contract.fees
+    }
+
+    pub fn get_admin_op(index: U64) -> Option<AdminOp> {
+        let contract = Self::load();
+        contract.admin_ops.get(&index).cloned()
+    }
+
+    /// Retrieve source ops from the op log, oldest first
+    ///
+    /// Op log indices start at `1`. A page covers the indices in
+    /// `cursor + 1..=cursor + limit`, clamped to the latest index; indices
+    /// holding admin ops are covered but not returned, so a page may hold
+    /// fewer than `limit` items. `next_cursor` is the last covered index,
+    /// even when nothing was returned, and `has_more` tells whether the log
+    /// goes past it.
+    ///
+    /// If `cursor` is before the compacted part of the log, nothing is
+    /// returned and `resync` is set, with `next_cursor` at the latest index
+    ///
+    /// - `cursor`: Last op log index already seen, `0` to start over
+    /// - `limit`: Maximum number of indices to cover, capped at
+    ///   `MAX_PAGE_SIZE`
+    pub fn get_sources_from(cursor: U64, limit: u32) -> SourceLog {
+        let contract = Self::load();
+        let last_index = contract.index.0;
+        if cursor < contract.compacted_to {
+            return SourceLog {
+                compacted_to: contract.compacted_to,
+                resync: true,
+                ops: Page {
+                    items: vec![],
+                    next_cursor: U64::from(last_index),
+                    has_more: false,
+                },
+            };
+        }
+
+        let ops = Page::from_log(
+            cursor,
+            Self::page_limit(limit),
+            last_index,
+            |index| {
+                contract.sources.get(&index).map(|source_op| IndexedSourceOp {
+                    index,
+                    source_op: source_op.clone(),
+                })
+            },
+        );
+        SourceLog { compacted_to: contract.compacted_to, resync: false, ops }
+    }
+
+    pub fn get_source(index: U64) -> Option<EventSourceOp> {
+        let contract = Self::load();
+        contract.sources.get(&index).cloned()
+    }
+
+    /// Retrieve currently registered sources, in registration order
+    ///
+    /// Ids are covered in ranges of `ACTIVE_BUCKET_SIZE`, at most `limit`
+    /// of them per page, so a page may hold fewer than `limit` sources
+    /// while `has_more` is set. `next_cursor` is the last id covered
+    ///
+    /// - `cursor`: Id of the last source already seen, `0` to start over
+    /// - `limit`: Maximum number of sources to return, capped at
+    ///   `MAX_PAGE_SIZE`
+    pub fn get_active_sources(cursor: U64, limit: u32) -> Page<ActiveSource> {
+        let contract = Self::load();
+        let ids = Page::from_buckets(
+            cursor,
+            Self::page_limit(limit),
+            ACTIVE_BUCKET_SIZE,
+            contract.index.0,
+            |bucket| contract.active_bucket(bucket),
+        );
+        contract.resolve_page(ids)
+    }
+
+    /// Retrieve active sources feeding a flow, paused ones included, in
+    /// registration order
+    ///
+    /// - `flow_contract_address`: Address of the flow contract, matched
+    ///   case-insensitively, with or without `0x`
+    /// - `cursor`: Id of the last source already seen, `0` to start over
+    /// - `limit`: Maximum number of sources to return, capped at
+    ///   `MAX_PAGE_SIZE`
+    pub fn get_sources_by_flow(
+        flow_contract_address: String,
+        cursor: U64,
+        limit: u32,
+    ) -> Page<ActiveSource> {
+        let contract = Self::load();
+        let ids = contract
+            .flow_sources
+            .get(&EventSource::normalize_hex(&flow_contract_address))
+            .cloned()
+            .unwrap_or_default();
+        contract.active_sources_page(&ids, cursor, limit)
+    }
+
+    /// Retrieve active sources on a chain, paused ones included, in
+    /// registration order
+    ///
+    /// Pages are cut as by `get_active_sources`, so a page may hold fewer
+    /// than `limit` sources while `has_more` is set. Comparing `version`
+    /// with the one from a previous call tells whether the chain's sources
+    /// changed since, see `get_chain_version`
+    ///
+    /// - `chain`: Chain of the sources
+    /// - `cursor`: Id of the last source already seen, `0` to start over
+    /// - `limit`: Maximum number of sources to return, capped at
+    ///   `MAX_PAGE_SIZE`
+    pub fn get_sources_by_chain(
+        chain: ChainId,
+        cursor: U64,
+        limit: u32,
+    ) -> ChainSources {
+        let contract = Self::load();
+        let ids = Page::from_buckets(
+            cursor,
+            Self::page_limit(limit),
+            ACTIVE_BUCKET_SIZE,
+            contract.index.0,
+            |bucket| contract.chain_bucket(&chain, bucket),
+        );
+        ChainSources {
+            version: contract.chain_version(&chain),
+            sources: contract.resolve_page(ids),
+        }
+    }
+
+    /// Retrieve the version of a chain's source set, which changes whenever
+    /// a source on the chain is created, updated, paused, resumed or removed
+    ///
+    /// - `chain`: Chain of the sources
+    pub fn get_chain_version(chain: ChainId) -> U64 {
+        let contract = Self::load();
+        contract.chain_version(&chain)
+    }
+
+    /// Retrieve active sources of a flow watching an event type of a contract
+    ///
+    /// Addresses are matched case-insensitively, with or without `0x`
+    ///
+    /// - `flow_contract_address`: Address of the flow contract
+    /// - `chain`: Chain of the source contract
+    /// - `smart_contract_address`: Address of the source contract
+    /// - `event_type`: Watched event type
+    pub fn find_source(
+        flow_contract_address: String,
+        chain: ChainId,
+        smart_contract_address: String,
+        event_type: String,
+    ) -> Vec<ActiveSource> {
+        let contract = Self::load();
+        // Event signatures are stored canonicalized
+        let event_type = EventSignature::parse(&event_type)
+            .map_or(event_type, |event_signature| event_signature.signature);
+        let lookup_key = EventSource::lookup_key(
+            &flow_contract_address,
+            &chain,
+            &smart_contract_address,
+            &event_type,
+        );
+        contract
+            .source_lookup
+            .get(&lookup_key)
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| contract.active_sources.get(id).cloned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Retrieve the id of a flow's active source from its source id
+    ///
+    /// - `flow_contract_address`: Address of the flow contract
+    /// - `source_id`: Source id the source was registered with
+    pub fn resolve_source(
+        flow_contract_address: String,
+        source_id: String,
+    ) -> Option<U64> {
+        let contract = Self::load();
+        contract
+            .source_ids
+            .get(&EventSource::source_id_key(
+                &flow_contract_address,
+                &source_id,
+            ))
+            .copied()
+    }
+
+    /// Retrieve the source id an active source was registered with, as
+    /// passed to its flow
+    ///
+    /// - `id`: Id of the source
+    pub fn resolve_source_id(id: U64) -> Option<String> {
+        let contract = Self::load();
+        contract
+            .active_sources
+            .get(&id)
+            .map(|active_source| active_source.event_source.source_id.clone())
+    }
+
+    /// Canonicalize a Solidity event declaration and compute its topic0, as
+    /// done when registering an EVM contract event source
+    ///
+    /// - `event_signature`: e.g. "SwapExecuted(bytes32 indexed, address,
+    ///   address, uint256, address)"
+    pub fn compute_topic0(
+        event_signature: String,
+    ) -> Result<EventSignature, RegistryError> {
+        EventSignature::parse(&event_signature)
+    }
+
+    /// Whether the source is registered and not removed, paused or not
+    ///
+    /// - `id`: Id of the source
+    pub fn is_source_active(id: U64) -> bool {
+        let contract = Self::load();
+        contract.active_sources.contains_key(&id)
+    }
+
+    /// Whether the source is registered and its event delivery suspended
+    ///
+    /// - `id`: Id of the source
+    pub fn is_source_paused(id: U64) -> bool {
+        let contract = Self::load();
+        contract
+            .active_sources
+            .get(&id)
+            .is_some_and(|active_source| active_source.paused)
+    }
+}
+
+/// Source as stored by storage version 1
+#[derive(Clone, BorshSerialize, BorshDeserialize)]
+struct EventSourceV1 {
+    flow_contract_address: String,
+    source_id: String,
+    chain: String,
+    source_type: String,
+    smart_contract_address: String,
+    event_type: String,
+    event_filters: Vec<String>,
+}
+
+impl EventSourceV1 {
+    /// Convert to the current layout
+    ///
+    /// Filters become a topic0 set, and finality defaults from the chain
+    /// catalog, or to no confirmations as before finality policies. Event
+    /// types that don't parse as a signature are kept as they are
+    ///
+    /// - `chain`: Catalog entry of the source's chain, if any
+    fn upgrade(self, chain: Option<&ChainInfo>) -> EventSource {
+        let mut event_source = EventSource {
+            flow_contract_address: self.flow_contract_address,
+            source_id: self.source_id,
+            chain: ChainId(self.chain),
+            source_type: SourceType::from(self.source_type.as_str()),
+            smart_contract_address: self.smart_contract_address,
+            event_type: self.event_type,
+            event_filters: TopicFilter::from_flat(self.event_filters),
+            from_block: None,
+            to_block: None,
+            finality: chain.map_or(
+                FinalityPolicy::Confirmations(0),
+                ChainInfo::default_finality,
+            ),
+            topic0: None,
+        };
+        if let Some(chain) = chain {
+            let _ = event_source.derive_topic0(chain);
+        }
+        event_source
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+enum OperationV1 {
+    Create,
+    Remove,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct EventSourceOpV1 {
+    event_source: EventSourceV1,
+    op: OperationV1,
+}
+
+/// Registry as stored by storage version 1, under `STORAGE_CONTRACT_KEY`
+#[derive(BorshSerialize, BorshDeserialize)]
+struct SourceRegistryV1 {
+    sources: LookupMap<U64, EventSourceOpV1>,
+    index: U64,
+}
+
+/// Registry rebuilt from version 1 storage so far, saved between
+/// `migrate` calls
+#[derive(BorshSerialize, BorshDeserialize)]
+struct MigrationV1 {
+    contract: SourceRegistry,
+    /// Index of the next version 1 op to replay
+    next_index: U64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    const SWAP_INITIATED_TOPIC0: &str =
+        "0x2034c67e4bc4e992b74d2ee2ed56444a50f9a2cfb45dc6ffaa73152049c3b648";
+
+    /// Op log with the given entries; missing indices hold admin ops
+    fn log_page(
+        entries: &[(u64, &'static str)],
+        last_index: u64,
+        cursor: u64,
+        limit: usize,
+    ) -> Page<&'static str> {
+        let log: BTreeMap<u64, &str> = entries.iter().copied().collect();
+        Page::from_log(U64::from(cursor), limit, last_index, |index| {
+            log.get(&index.0).copied()
+        })
+    }
+
+    fn bucket_page(
+        ids: &[u64],
+        last_id: u64,
+        cursor: u64,
+        limit: usize,
+    ) -> Page<U64> {
+        Page::from_buckets(U64::from(cursor), limit, 10, last_id, |bucket| {
+            ids.iter()
+                .copied()
+                .filter(|id| id / 10 == bucket)
+                .map(U64::from)
+                .collect()
+        })
+    }
+
+    fn topic(byte: char) -> String {
+        format!("0x{}", byte.to_string().repeat(64))
+    }
+
+    fn chain(name: &str) -> ChainInfo {
+        ChainInfo::defaults()
+            .into_iter()
+            .find(|chain| chain.name.as_str() == name)
+            .unwrap()
+    }
+
+    fn evm_source() -> EventSource {
+        EventSource {
+            flow_contract_address: format!("0x{}", "ab".repeat(20)),
+            source_id: "swap-initiated".to_string(),
+            chain: ChainId::from("ethereum_goerli"),
+            source_type: SourceType::SmartContractEvent,
+            smart_contract_address: format!("0x{}", "cd".repeat(20)),
+            event_type: "SwapInitiated(bytes32,address,uint256)".to_string(),
+            event_filters: TopicFilter::default(),
+            from_block: None,
+            to_block: None,
+            finality: FinalityPolicy::Confirmations(12),
+            topic0: None,
+        }
+    }
+
+    fn legacy_source(chain: &str, event_type: &str) -> EventSourceV1 {
+        EventSourceV1 {
+            flow_contract_address: format!("0x{}", "ab".repeat(20)),
+            source_id: "swap-initiated".to_string(),
+            chain: chain.to_string(),
+            source_type: "smart_contract_event".to_string(),
+            smart_contract_address: format!("0x{}", "cd".repeat(20)),
+            event_type: event_type.to_string(),
+            event_filters: vec![topic('a'), topic('b')],
+        }
+    }
+
+    #[test]
+    fn log_page_starts_at_index_one_from_cursor_zero() {
+        let page = log_page(&[(1, "a"), (2, "b"), (3, "c")], 3, 0, 2);
+        assert_eq!(page.items, vec!["a", "b"]);
+        assert_eq!(page.next_cursor, U64::from(2));
+        assert!(page.has_more);
+    }
+
+    #[test]
+    fn log_page_returns_newest_op() {
+        let page = log_page(&[(1, "a"), (2, "b"), (3, "c")], 3, 2, 10);
+        assert_eq!(page.items, vec!["c"]);
+        assert_eq!(page.next_cursor, U64::from(3));
+        assert!(!page.has_more);
+    }
+
+    #[test]
+    fn log_page_cursor_moves_past_admin_ops() {
+        let entries = [(1, "a"), (4, "d")];
+        let page = log_page(&entries, 4, 1, 2);
+        assert!(page.items.is_empty());
+        assert_eq!(page.next_cursor, U64::from(3));
+        assert!(page.has_more);
+
+        let page = log_page(&entries, 4, page.next_cursor.0, 2);
+        assert_eq!(page.items, vec!["d"]);
+        assert_eq!(page.next_cursor, U64::from(4));
+        assert!(!page.has_more);
+    }
+
+    #[test]
+    fn log_page_has_more_at_boundary() {
+        let entries = [(1, "a"), (2, "b"), (3, "c")];
+        assert!(!log_page(&entries, 3, 0, 3).has_more);
+        assert!(log_page(&entries, 3, 0, 2).has_more);
+        assert!(!log_page(&entries, 3, 3, 1).has_more);
+    }
+
+    #[test]
+    fn log_page_keeps_cursor_past_last_index() {
+        let page = log_page(&[(1, "a")], 1, 5, 10);
+        assert!(page.items.is_empty());
+        assert_eq!(page.next_cursor, U64::from(5));
+        assert!(!page.has_more);
+    }
+
+    #[test]
+    fn page_limit_is_clamped() {
+        assert_eq!(SourceRegistry::page_limit(0), 1);
+        assert_eq!(SourceRegistry::page_limit(7), 7);
+        assert_eq!(
+            SourceRegistry::page_limit(u32::MAX),
+            MAX_PAGE_SIZE as usize
+        );
+    }
+
+    #[test]
+    fn bucket_page_walks_ids_in_order() {
+        let ids = [1, 2, 15, 37];
+        let page = bucket_page(&ids, 40, 0, 2);
+        assert_eq!(page.items, vec![U64::from(1), U64::from(2)]);
+        assert_eq!(page.next_cursor, U64::from(9));
+        assert!(page.has_more);
+
+        let page = bucket_page(&ids, 40, 9, 10);
+        assert_eq!(page.items, vec![U64::from(15), U64::from(37)]);
+        assert_eq!(page.next_cursor, U64::from(40));
+        assert!(!page.has_more);
+    }
+
+    #[test]
+    fn bucket_page_stops_mid_bucket_when_full() {
+        let page = bucket_page(&[11, 12, 13], 20, 0, 2);
+        assert_eq!(page.items, vec![U64::from(11), U64::from(12)]);
+        assert_eq!(page.next_cursor, U64::from(12));
+        assert!(page.has_more);
+    }
+
+    #[test]
+    fn bucket_page_reads_at_most_limit_buckets() {
+        let page = bucket_page(&[1, 45], 50, 1, 2);
+        assert!(page.items.is_empty());
+        assert_eq!(page.next_cursor, U64::from(19));
+        assert!(page.has_more);
+    }
+
+    #[test]
+    fn event_signature_is_canonicalized() {
+        let event_signature = EventSignature::parse(
+            "event SwapInitiated(bytes32 indexed globalTxId, address indexed \
+             inToken, uint256 inAmount, string, string, address, uint256, \
+             address)",
+        )
+        .unwrap();
+        assert_eq!(
+            event_signature.signature,
+            "SwapInitiated(bytes32,address,uint256,string,string,address,\
+             uint256,address)"
+        );
+        assert_eq!(event_signature.topic0, SWAP_INITIATED_TOPIC0);
+    }
+
+    #[test]
+    fn event_signature_keeps_tuples() {
+        let event_signature =
+            EventSignature::parse("Filled((uint256,address) order, bool)")
+                .unwrap();
+        assert_eq!(event_signature.signature, "Filled((uint256,address),bool)");
+    }
+
+    #[test]
+    fn event_signature_rejects_garbage() {
+        assert!(matches!(
+            EventSignature::parse("SwapInitiated"),
+            Err(RegistryError::InvalidEventSignature(_))
+        ));
+    }
+
+    #[test]
+    fn topic_filter_reads_positions() {
+        let filter: TopicFilter =
+            serde_json::from_str(&format!(r#"[["{}"], null]"#, topic('a')))
+                .unwrap();
+        assert_eq!(filter, TopicFilter(vec![Some(vec![topic('a')]), None]));
+    }
+
+    #[test]
+    fn topic_filter_reads_flat_list_as_topic0_set() {
+        let filter: TopicFilter = serde_json::from_str(&format!(
+            r#"["{}", "{}"]"#,
+            topic('a'),
+            topic('b')
+        ))
+        .unwrap();
+        assert_eq!(
+            filter,
+            TopicFilter(vec![Some(vec![topic('a'), topic('b')])])
+        );
+
+        let filter: TopicFilter = serde_json::from_str("[]").unwrap();
+        assert_eq!(filter, TopicFilter::default());
+    }
+
+    #[test]
+    fn topic_filter_normalizes_equivalent_filters() {
+        let filter = TopicFilter(vec![
+            Some(vec![topic('B'), topic('a'), topic('b')]),
+            None,
+            None,
+        ]);
+        assert_eq!(
+            filter.normalized(),
+            vec![Some(vec!["a".repeat(64), "b".repeat(64)])]
+        );
+    }
+
+    #[test]
+    fn topic_filter_validates_shape_and_values() {
+        assert!(TopicFilter(vec![None; 4]).validate(None).is_ok());
+        assert!(matches!(
+            TopicFilter(vec![None; 5]).validate(None),
+            Err(RegistryError::InvalidTopicFilter(_))
+        ));
+        assert!(matches!(
+            TopicFilter(vec![None, Some(vec![])]).validate(None),
+            Err(RegistryError::InvalidTopicFilter(_))
+        ));
+        assert!(matches!(
+            TopicFilter(vec![Some(vec!["0x12".to_string()])]).validate(None),
+            Err(RegistryError::InvalidEventFilter(_))
+        ));
+    }
+
+    #[test]
+    fn topic_filter_topic0_set_must_allow_event_signature() {
+        let topic0 = SWAP_INITIATED_TOPIC0.to_string();
+        let allowing = TopicFilter(vec![Some(vec![
+            topic('a'),
+            topic0.to_uppercase().replacen("0X", "0x", 1),
+        ])]);
+        assert!(allowing.validate(Some(&topic0)).is_ok());
+        assert!(TopicFilter(vec![None, Some(vec![topic('a')])])
+            .validate(Some(&topic0))
+            .is_ok());
+        assert!(matches!(
+            TopicFilter(vec![Some(vec![topic('a')])]).validate(Some(&topic0)),
+            Err(RegistryError::InvalidTopicFilter(_))
+        ));
+    }
+
+    #[test]
+    fn l1_finalized_is_only_supported_on_rollups() {
+        let chains = ChainInfo::defaults();
+        let l1 = chains.iter().find(|c| c.parent_chain.is_none()).unwrap();
+        let rollup = chains.iter().find(|c| c.parent_chain.is_some()).unwrap();
+        assert!(!FinalityPolicy::L1Finalized.is_supported_on(l1));
+        assert!(FinalityPolicy::L1Finalized.is_supported_on(rollup));
+        assert!(FinalityPolicy::Finalized.is_supported_on(l1));
+    }
+
+    #[test]
+    fn validate_accepts_well_formed_source() {
+        assert!(evm_source().validate(&chain("ethereum_goerli")).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_bad_flow_address() {
+        let source = EventSource {
+            flow_contract_address: "not an address".to_string(),
+            ..evm_source()
+        };
+        assert!(matches!(
+            source.validate(&chain("ethereum_goerli")),
+            Err(RegistryError::InvalidFlowAddress(_))
+        ));
+    }
+
+    #[test]
+    fn validate_rejects_empty_source_id() {
+        let source =
+            EventSource { source_id: "  ".to_string(), ..evm_source() };
+        assert!(matches!(
+            source.validate(&chain("ethereum_goerli")),
+            Err(RegistryError::InvalidSourceId(_))
+        ));
+    }
+
+    #[test]
+    fn validate_rejects_source_type_of_other_chain_family() {
+        let source = EventSource {
+            source_type: SourceType::SolanaProgramLog,
+            ..evm_source()
+        };
+        assert!(matches!(
+            source.validate(&chain("ethereum_goerli")),
+            Err(RegistryError::IncompatibleSourceType { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_rejects_malformed_contract_address() {
+        for address in ["cd".repeat(20), "0x1234".to_string()] {
+            let source =
+                EventSource { smart_contract_address: address, ..evm_source() };
+            assert!(matches!(
+                source.validate(&chain("ethereum_goerli")),
+                Err(RegistryError::InvalidContractAddress(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn validate_rejects_inverted_block_range() {
+        let source = EventSource {
+            from_block: Some(U64::from(10)),
+            to_block: Some(U64::from(5)),
+            ..evm_source()
+        };
+        assert!(matches!(
+            source.validate(&chain("ethereum_goerli")),
+            Err(RegistryError::InvalidBlockRange { .. })
+        ));
+        let source = EventSource {
+            from_block: Some(U64::from(10)),
+            to_block: Some(U64::from(10)),
+            ..evm_source()
+        };
+        assert!(source.validate(&chain("ethereum_goerli")).is_ok());
+    }
+
+    #[test]
+    fn source_type_decodes_legacy_borsh_strings() {
+        let known = "smart_contract_event".to_string().try_to_vec().unwrap();
+        assert_eq!(
+            SourceType::try_from_slice(&known).unwrap(),
+            SourceType::SmartContractEvent
+        );
+        let unknown = "smart_contract_evnt".to_string().try_to_vec().unwrap();
+        assert_eq!(
+            SourceType::try_from_slice(&unknown).unwrap(),
+            SourceType::Unknown("smart_contract_evnt".to_string())
+        );
+        assert_eq!(
+            SourceType::BlockHeader.try_to_vec().unwrap(),
+            "block_header".to_string().try_to_vec().unwrap()
+        );
+    }
+
+    #[test]
+    fn source_type_decodes_legacy_json_strings() {
+        assert_eq!(
+            serde_json::from_str::<SourceType>("\"smart_contract_event\"")
+                .unwrap(),
+            SourceType::SmartContractEvent
+        );
+        assert_eq!(
+            serde_json::from_str::<SourceType>("\"smart_contract_evnt\"")
+                .unwrap(),
+            SourceType::Unknown("smart_contract_evnt".to_string())
+        );
+        assert_eq!(
+            serde_json::to_string(&SourceType::NativeTransfer).unwrap(),
+            "\"native_transfer\""
+        );
+    }
+
+    #[test]
+    fn legacy_source_upgrade_reads_flat_filters_as_topic0_set() {
+        let goerli = chain("ethereum_goerli");
+        let source = legacy_source("ethereum_goerli", "SwapInitiated")
+            .upgrade(Some(&goerli));
+        assert_eq!(
+            source.event_filters,
+            TopicFilter(vec![Some(vec![topic('a'), topic('b')])])
+        );
+        let source = EventSourceV1 {
+            event_filters: vec![],
+            ..legacy_source("ethereum_goerli", "SwapInitiated")
+        }
+        .upgrade(Some(&goerli));
+        assert_eq!(source.event_filters, TopicFilter::default());
+    }
+
+    #[test]
+    fn legacy_source_upgrade_takes_chain_default_finality() {
+        let goerli = chain("ethereum_goerli");
+        let source = legacy_source("ethereum_goerli", "SwapInitiated")
+            .upgrade(Some(&goerli));
+        assert_eq!(source.finality, goerli.default_finality());
+        assert_eq!(source.chain, goerli.name);
+    }
+
+    #[test]
+    fn legacy_source_upgrade_on_unknown_chain_waits_for_no_confirmations() {
+        let source =
+            legacy_source("moonbase", "Transfer(address,address,uint256)")
+                .upgrade(None);
+        assert_eq!(source.finality, FinalityPolicy::Confirmations(0));
+        assert_eq!(source.event_type, "Transfer(address,address,uint256)");
+        assert_eq!(source.topic0, None);
+    }
+
+    #[test]
+    fn legacy_source_upgrade_keeps_unparsable_event_type() {
+        let goerli = chain("ethereum_goerli");
+        let source = legacy_source("ethereum_goerli", "SwapInitiated")
+            .upgrade(Some(&goerli));
+        assert_eq!(source.event_type, "SwapInitiated");
+        assert_eq!(source.topic0, None);
+
+        let source = legacy_source(
+            "ethereum_goerli",
+            "event SwapInitiated(bytes32 indexed globalTxId, address indexed \
+             inToken, uint256 inAmount, string, string, address, uint256, \
+             address)",
+        )
+        .upgrade(Some(&goerli));
+        assert_eq!(source.topic0.as_deref(), Some(SWAP_INITIATED_TOPIC0));
+    }
+
+    #[test]
+    fn source_update_applies_only_set_fields() {
+        let mut source = evm_source();
+        SourceUpdate {
+            finality: Some(FinalityPolicy::Finalized),
+            ..SourceUpdate::default()
+        }
+        .apply(&mut source);
+        assert_eq!(
+            source,
+            EventSource { finality: FinalityPolicy::Finalized, ..evm_source() }
+        );
+    }
+
+    #[test]
+    fn source_update_diff_carries_only_changed_fields() {
+        let previous = evm_source();
+        let current = EventSource {
+            event_filters: TopicFilter(vec![None, Some(vec![topic('a')])]),
+            ..previous.clone()
+        };
+        assert_eq!(
+            SourceUpdate::diff(&previous, &current),
+            SourceUpdate {
+                event_filters: Some(current.event_filters.clone()),
+                ..SourceUpdate::default()
+            }
+        );
+        assert_eq!(
+            SourceUpdate::diff(&previous, &previous),
+            SourceUpdate::default()
+        );
+    }
+
+    #[test]
+    fn identity_ignores_address_case_and_prefix() {
+        let source = evm_source();
+        let respelled = EventSource {
+            flow_contract_address: "AB".repeat(20),
+            smart_contract_address: format!("0X{}", "CD".repeat(20)),
+            ..evm_source()
+        };
+        assert_eq!(source.identity(), respelled.identity());
+        assert_eq!(source.source_lookup_key(), respelled.source_lookup_key());
+    }
+
+    #[test]
+    fn identity_ignores_equivalent_filter_spellings() {
+        let filtered = EventSource {
+            event_filters: TopicFilter(vec![
+                None,
+                Some(vec![topic('a'), topic('b')]),
+            ]),
+            ..evm_source()
+        };
+        let respelled = EventSource {
+            event_filters: TopicFilter(vec![
+                None,
+                Some(vec![
+                    topic('B').replacen("0x", "0X", 1),
+                    topic('a'),
+                    topic('A'),
+                ]),
+                None,
+            ]),
+            ..evm_source()
+        };
+        assert_eq!(filtered.identity(), respelled.identity());
+        let other = EventSource {
+            event_filters: TopicFilter(vec![None, Some(vec![topic('a')])]),
+            ..evm_source()
+        };
+        assert_ne!(filtered.identity(), other.identity());
+    }
+}
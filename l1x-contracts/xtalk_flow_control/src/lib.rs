@@ -10,6 +10,7 @@ use std::str::FromStr;
 const STORAGE_CONTRACT_KEY: &[u8; 21] = b"cross-chain-swap-flow";
 const STORAGE_EVENTS_KEY: &[u8; 6] = b"events";
 const STORAGE_STATE_KEY: &[u8; 8] = b"payloads";
+const STORAGE_ADMINS_KEY: &[u8; 6] = b"admins";
 
 const PAYLOAD_1: &str = "execute_swap";
 const PAYLOAD_2: &str = "finalize_swap";
@@ -248,10 +249,81 @@ impl CrossChainSwapFlow {
         global_tx_id.to_owned() + event_type
     }
 
+    /// Load flow admins from storage
+    ///
+    /// Admins are stored apart from the contract, so that flows deployed
+    /// before admins existed still load. Such flows are administered by the
+    /// contract owner
+    fn load_admins() -> Vec<l1x_sdk::types::Address> {
+        match l1x_sdk::storage_read(STORAGE_ADMINS_KEY) {
+            Some(bytes) => match Vec::try_from_slice(&bytes) {
+                Ok(admins) => admins,
+                Err(_) => {
+                    panic!("Unable to parse admins bytes")
+                }
+            },
+            None => vec![l1x_sdk::contract_owner_address()],
+        }
+    }
+
+    /// Save flow admins to storage
+    ///
+    /// - `admins`: Addresses allowed to administer this flow
+    fn save_admins(admins: &[l1x_sdk::types::Address]) {
+        match borsh::BorshSerialize::try_to_vec(admins) {
+            Ok(encoded_admins) => {
+                l1x_sdk::storage_write(STORAGE_ADMINS_KEY, &encoded_admins);
+            }
+            Err(_) => panic!("Unable to save admins"),
+        };
+    }
+
+    /// Panic unless the caller is the contract owner
+    fn ensure_owner() {
+        if l1x_sdk::caller_address() != l1x_sdk::contract_owner_address() {
+            panic!("Caller is not the contract owner");
+        }
+    }
+
     /// Instantiate and save contract to storage
+    ///
+    /// The caller becomes the first contract admin
     pub fn new() {
         let mut contract = Self::default();
         contract.save();
+        Self::save_admins(&[l1x_sdk::caller_address()]);
+    }
+
+    /// Retrieve addresses allowed to administer this flow, e.g. to register
+    /// event sources for it in the source registry
+    pub fn get_contract_admins() -> Vec<l1x_sdk::types::Address> {
+        Self::load_admins()
+    }
+
+    /// Allow an address to administer this flow. Contract owner only
+    ///
+    /// - `admin`: Address of the new admin
+    pub fn add_admin(admin: l1x_sdk::types::Address) {
+        Self::ensure_owner();
+        let mut admins = Self::load_admins();
+        if admins.contains(&admin) {
+            panic!("{} is already an admin", admin);
+        }
+        admins.push(admin);
+        Self::save_admins(&admins);
+    }
+
+    /// Revoke an admin of this flow. Contract owner only
+    ///
+    /// - `admin`: Address of the admin to remove
+    pub fn remove_admin(admin: l1x_sdk::types::Address) {
+        Self::ensure_owner();
+        let mut admins = Self::load_admins();
+        if !admins.contains(&admin) {
+            panic!("{} is not an admin", admin);
+        }
+        admins.retain(|address| *address != admin);
+        Self::save_admins(&admins);
     }
 
     /// Save event to contract storage